@@ -0,0 +1,14 @@
+use crate::api::{response::ApiResponse, ApiVersion};
+
+pub mod response;
+
+const VERSION: ApiVersion = ApiVersion::V2;
+
+#[derive(Debug)]
+pub struct AccountApi<'a>(pub(crate) &'a crate::OpsgenieClient);
+
+impl<'a> AccountApi<'a> {
+    pub async fn get(&self) -> crate::Result<ApiResponse<self::response::Account>> {
+        self.0.get(VERSION, "account", &()).await
+    }
+}
@@ -0,0 +1,32 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Plan {
+    pub max_user_count: u64,
+    pub name: String,
+    pub is_yearly: bool,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Account {
+    pub name: String,
+    pub user_count: u64,
+    pub plan: Plan,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::replay_client;
+
+    #[tokio::test]
+    async fn get_account_response() {
+        let client = replay_client("account/get_account_response");
+        let account = client.account().get().await.unwrap().data;
+        assert_eq!(account.name, "opsgenie");
+        assert_eq!(account.user_count, 1450);
+        assert_eq!(account.plan.name, "Enterprise");
+        assert_eq!(account.plan.max_user_count, 1500);
+    }
+}
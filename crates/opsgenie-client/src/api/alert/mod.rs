@@ -1,7 +1,28 @@
-use crate::{api::response::ApiResponse, query_builder::ToFilter};
+use crate::{
+    api::{
+        response::{ApiResponse, NoData},
+        ApiVersion,
+    },
+    pagination::Pagination,
+    query_builder::ToFilter,
+};
+use serde::Serialize;
 
+pub mod request;
 pub mod response;
 
+/// Type of the identifier used to look up an alert.
+#[derive(Debug, Default, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IdentifierType {
+    #[default]
+    Id,
+    Tiny,
+    Alias,
+}
+
+const VERSION: ApiVersion = ApiVersion::V2;
+
 #[derive(Debug)]
 pub struct AlertApi<'a>(pub(crate) &'a crate::OpsgenieClient);
 
@@ -12,7 +33,9 @@ impl<'a> AlertApi<'a> {
     ) -> crate::Result<ApiResponse<self::response::Count>> {
         let query = query.to_filter();
         tracing::debug!(query=%query, "Sending query");
-        self.0.get("alerts/count", &[("query", query)]).await
+        self.0
+            .get(VERSION, "alerts/count", &[("query", query)])
+            .await
     }
 
     pub async fn list(
@@ -24,7 +47,59 @@ impl<'a> AlertApi<'a> {
         let query = query.to_filter();
         tracing::debug!(query=%query, "Sending query");
         self.0
-            .get("alerts", &[("query", query), ("limit", limit.to_string())])
+            .get(
+                VERSION,
+                "alerts",
+                &[("query", query), ("limit", limit.to_string())],
+            )
+            .await
+    }
+
+    /// Lists a page of the alerts matching the query.
+    /// Alerts can be sorted by `createdAt`, `updatedAt`, `tinyId`, etc.
+    pub async fn list_page(
+        &self,
+        query: impl ToFilter,
+        pagination: &Pagination,
+    ) -> crate::Result<ApiResponse<Vec<self::response::Alert>>> {
+        #[derive(Serialize)]
+        struct ListQuery<'a> {
+            query: String,
+            #[serde(flatten)]
+            pagination: &'a Pagination,
+        }
+
+        let query = query.to_filter();
+        tracing::debug!(query=%query, "Sending query");
+        self.0
+            .get(VERSION, "alerts", &ListQuery { query, pagination })
+            .await
+    }
+
+    /// Creates an alert.
+    /// Alerts are created asynchronously, so the response contains no data.
+    pub async fn create(
+        &self,
+        request: &self::request::CreateAlert,
+    ) -> crate::Result<ApiResponse<NoData>> {
+        self.0.post(VERSION, "alerts", &(), request).await
+    }
+
+    /// Closes an alert.
+    /// Alerts are closed asynchronously, so the response contains no data.
+    pub async fn close(
+        &self,
+        identifier: &str,
+        identifier_type: IdentifierType,
+        request: &self::request::CloseAlert,
+    ) -> crate::Result<ApiResponse<NoData>> {
+        self.0
+            .post(
+                VERSION,
+                &format!("alerts/{}/close", identifier),
+                &[("identifierType", identifier_type)],
+                request,
+            )
             .await
     }
 }
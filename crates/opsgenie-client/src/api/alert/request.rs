@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+/// Team, user, escalation or schedule that an alert is routed to.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Responder {
+    pub r#type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+}
+
+impl Responder {
+    pub fn team(name: impl Into<String>) -> Self {
+        Self {
+            r#type: "team".into(),
+            id: None,
+            name: Some(name.into()),
+            username: None,
+        }
+    }
+
+    pub fn user(username: impl Into<String>) -> Self {
+        Self {
+            r#type: "user".into(),
+            id: None,
+            name: None,
+            username: Some(username.into()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateAlert {
+    pub message: String,
+    /// Alerts with the same alias are deduplicated by Opsgenie.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub responders: Vec<Responder>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub details: HashMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entity: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority: Option<String>,
+}
+
+impl CreateAlert {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            ..Self::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CloseAlert {
+    /// Display name of the request owner.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// Note added to the alert when it is closed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
@@ -0,0 +1,116 @@
+use tokio::io::{AsyncWrite, AsyncWriteExt as _};
+use url::Url;
+
+use crate::api::{response::ApiResponse, ApiVersion};
+
+pub mod response;
+
+const VERSION: ApiVersion = ApiVersion::V2;
+
+#[derive(Debug)]
+pub struct AuditLogApi<'a>(pub(crate) &'a crate::OpsgenieClient);
+
+impl<'a> AuditLogApi<'a> {
+    /// Lists the log files available after the provided `marker`.
+    ///
+    /// The marker to request the next page is returned in [`ApiResponse::marker`].
+    /// If `marker` is `None`, the listing starts from the oldest log file.
+    pub async fn list(
+        &self,
+        marker: Option<&str>,
+        limit: Option<u32>,
+    ) -> crate::Result<ApiResponse<Vec<self::response::LogFile>>> {
+        let path = format!("logs/list/{}", marker.unwrap_or_default());
+        self.0.get(VERSION, &path, &[("limit", limit)]).await
+    }
+
+    /// Lists all the log files available after the provided `marker`,
+    /// following the pagination markers.
+    pub async fn list_all(
+        &self,
+        marker: Option<&str>,
+    ) -> crate::Result<Vec<self::response::LogFile>> {
+        let mut marker = marker.map(str::to_owned);
+        let mut files = Vec::new();
+        loop {
+            let response = self
+                .list(marker.as_deref(), Some(crate::limits::LOG_FILES_MAX_LIMIT))
+                .await?;
+            if response.data.is_empty() {
+                break;
+            }
+            files.extend(response.data);
+            match response.marker {
+                Some(next) if !next.is_empty() && marker.as_deref() != Some(&next) => {
+                    marker = Some(next);
+                }
+                _ => break,
+            }
+        }
+        Ok(files)
+    }
+
+    /// Generates a temporary link to download the log file.
+    pub async fn download_link(&self, file_name: &str) -> crate::Result<Url> {
+        let link = self
+            .0
+            .get_text(VERSION, &format!("logs/download/{}", file_name))
+            .await?;
+        Ok(link.trim().parse()?)
+    }
+
+    /// Downloads the log file, streaming its contents to `writer`.
+    ///
+    /// Returns the number of bytes written.
+    pub async fn download<W: AsyncWrite + Unpin>(
+        &self,
+        file_name: &str,
+        writer: &mut W,
+    ) -> crate::Result<u64> {
+        let link = self.download_link(file_name).await?;
+        let mut response = self.0.download(link).await?;
+        let mut written = 0;
+        while let Some(chunk) = response.chunk().await? {
+            writer.write_all(&chunk).await?;
+            written += chunk.len() as u64;
+        }
+        writer.flush().await?;
+        Ok(written)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::replay_client;
+
+    #[tokio::test]
+    async fn list_all_follows_markers() {
+        let client = replay_client("audit_log/list_all_response");
+        let files = client.audit_log().list_all(None).await.unwrap();
+        let names: Vec<_> = files.iter().map(|file| file.filename.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "2018-07-10-05-49-20-Alert.json",
+                "2018-07-10-06-49-20-Alert.json",
+                "2018-07-10-07-49-20-Alert.json",
+            ]
+        );
+        assert_eq!(files[2].size, 2048);
+    }
+
+    #[tokio::test]
+    async fn download_streams_file() {
+        let client = replay_client("audit_log/download_response");
+        let mut contents = Vec::new();
+        let written = client
+            .audit_log()
+            .download("2018-07-10-05-49-20-Alert.json", &mut contents)
+            .await
+            .unwrap();
+        let expected = "{\"action\":\"Create\",\"alertId\":\"1\"}\n\
+                        {\"action\":\"Close\",\"alertId\":\"1\"}\n";
+        assert_eq!(String::from_utf8(contents).unwrap(), expected);
+        assert_eq!(written, expected.len() as u64);
+    }
+}
@@ -0,0 +1,23 @@
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogFile {
+    pub filename: String,
+    /// Creation time of the log file, in milliseconds since the Unix epoch.
+    pub date: u64,
+    /// Size of the log file in bytes.
+    pub size: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::replay_client;
+
+    #[tokio::test]
+    async fn list_log_files_response() {
+        let client = replay_client("audit_log/list_log_files_response");
+        let response = client.audit_log().list(None, Some(3)).await.unwrap();
+        assert_eq!(response.marker.as_deref(), Some("2018-07-10-06-49-20"));
+    }
+}
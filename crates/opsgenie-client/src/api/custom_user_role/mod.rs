@@ -0,0 +1,39 @@
+use serde::Serialize;
+
+use crate::api::{response::ApiResponse, ApiVersion};
+
+pub mod response;
+
+/// Type of the identifier used to look up a custom user role.
+#[derive(Debug, Default, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IdentifierType {
+    #[default]
+    Id,
+    Name,
+}
+
+const VERSION: ApiVersion = ApiVersion::V2;
+
+#[derive(Debug)]
+pub struct CustomUserRoleApi<'a>(pub(crate) &'a crate::OpsgenieClient);
+
+impl<'a> CustomUserRoleApi<'a> {
+    pub async fn list(&self) -> crate::Result<ApiResponse<Vec<self::response::CustomUserRole>>> {
+        self.0.get(VERSION, "roles", &()).await
+    }
+
+    pub async fn get(
+        &self,
+        identifier: &str,
+        identifier_type: IdentifierType,
+    ) -> crate::Result<ApiResponse<self::response::CustomUserRole>> {
+        self.0
+            .get(
+                VERSION,
+                &format!("roles/{}", identifier),
+                &[("identifierType", identifier_type)],
+            )
+            .await
+    }
+}
@@ -0,0 +1,45 @@
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomUserRole {
+    pub id: String,
+    pub name: String,
+    /// Built-in role this role extends, e.g. `user` or `observer`.
+    pub extended_role: Option<String>,
+    #[serde(default)]
+    pub granted_rights: Vec<String>,
+    #[serde(default)]
+    pub disallowed_rights: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{api::custom_user_role::IdentifierType, test_utils::replay_client};
+
+    #[tokio::test]
+    async fn list_roles_response() {
+        let client = replay_client("custom_user_role/list_roles_response");
+        let roles = client.custom_user_role().list().await.unwrap().data;
+        let names: Vec<_> = roles.iter().map(|role| role.name.as_str()).collect();
+        assert_eq!(names, ["Responder", "Viewer"]);
+        assert_eq!(roles[0].granted_rights, ["alert-action", "alert-delete"]);
+        assert_eq!(roles[0].disallowed_rights, ["configuration-access"]);
+        assert_eq!(roles[1].extended_role.as_deref(), Some("observer"));
+    }
+
+    #[tokio::test]
+    async fn get_role_response() {
+        let client = replay_client("custom_user_role/get_role_response");
+        let role = client
+            .custom_user_role()
+            .get("2b8ac5ed-3bcf-4d8d-9c4f-0ab1b2d1c9e0", IdentifierType::Id)
+            .await
+            .unwrap()
+            .data;
+        assert_eq!(role.name, "Responder");
+        assert_eq!(role.extended_role.as_deref(), Some("user"));
+        assert_eq!(role.granted_rights, ["alert-action"]);
+        assert!(role.disallowed_rights.is_empty());
+    }
+}
@@ -0,0 +1,51 @@
+use crate::{
+    api::{response::ApiResponse, ApiVersion},
+    pagination::Pagination,
+};
+
+pub mod request;
+pub mod response;
+
+const VERSION: ApiVersion = ApiVersion::V2;
+
+#[derive(Debug)]
+pub struct DeploymentApi<'a>(pub(crate) &'a crate::OpsgenieClient);
+
+impl<'a> DeploymentApi<'a> {
+    pub async fn create(
+        &self,
+        request: &self::request::CreateDeployment,
+    ) -> crate::Result<ApiResponse<self::response::DeploymentRef>> {
+        self.0.post(VERSION, "deployments", &(), request).await
+    }
+
+    pub async fn get(
+        &self,
+        deployment_id: &str,
+    ) -> crate::Result<ApiResponse<self::response::Deployment>> {
+        self.0
+            .get(VERSION, &format!("deployments/{}", deployment_id), &())
+            .await
+    }
+
+    pub async fn update_state(
+        &self,
+        deployment_id: &str,
+        state: self::response::DeploymentState,
+    ) -> crate::Result<ApiResponse<self::response::DeploymentRef>> {
+        self.0
+            .patch(
+                VERSION,
+                &format!("deployments/{}/state", deployment_id),
+                &self::request::UpdateDeploymentState { state },
+            )
+            .await
+    }
+
+    pub async fn list(
+        &self,
+        pagination: &Pagination,
+    ) -> crate::Result<ApiResponse<Vec<self::response::Deployment>>> {
+        self.0.get(VERSION, "deployments", pagination).await
+    }
+}
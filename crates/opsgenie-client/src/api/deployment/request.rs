@@ -0,0 +1,40 @@
+use serde::Serialize;
+
+use super::response::{DeploymentState, Environment};
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateDeployment {
+    pub message: String,
+    pub state: DeploymentState,
+    pub environment: Environment,
+    /// Identifiers of the services affected by the deployment.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub service_ids: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub link: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+}
+
+impl CreateDeployment {
+    pub fn new(message: impl Into<String>, environment: Environment) -> Self {
+        Self {
+            message: message.into(),
+            state: DeploymentState::Started,
+            environment,
+            service_ids: Vec::new(),
+            description: None,
+            link: None,
+            source: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct UpdateDeploymentState {
+    pub state: DeploymentState,
+}
@@ -0,0 +1,96 @@
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum DeploymentState {
+    Started,
+    Successful,
+    Failed,
+    Cancelled,
+}
+
+impl DeploymentState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Started => "started",
+            Self::Successful => "successful",
+            Self::Failed => "failed",
+            Self::Cancelled => "cancelled",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Environment {
+    /// Environment type, e.g. `production` or `staging`.
+    pub r#type: String,
+    pub id: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Deployment {
+    pub id: String,
+    pub message: String,
+    pub description: Option<String>,
+    pub state: DeploymentState,
+    pub environment: Environment,
+    #[serde(default)]
+    pub service_ids: Vec<String>,
+    pub link: Option<String>,
+    pub source: Option<String>,
+    pub created_at: DateTime<FixedOffset>,
+    pub updated_at: Option<DateTime<FixedOffset>>,
+}
+
+/// Returned by create and update requests.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeploymentRef {
+    pub id: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        api::deployment::request::CreateDeployment, pagination::Pagination,
+        test_utils::replay_client,
+    };
+
+    #[tokio::test]
+    async fn create_deployment_response() {
+        let client = replay_client("deployment/create_deployment_response");
+        let environment = Environment {
+            r#type: "production".into(),
+            id: "prod".into(),
+        };
+        let request = CreateDeployment::new("Release 1.0", environment);
+        let response = client.deployment().create(&request).await.unwrap();
+        assert_eq!(response.data.id, "3a6c4b1e-5c2a-4c71-8f7b-4a2f0d9b6e15");
+    }
+
+    #[tokio::test]
+    async fn list_deployments_response() {
+        let client = replay_client("deployment/list_deployments_response");
+        let deployments = client
+            .deployment()
+            .list(&Pagination::new())
+            .await
+            .unwrap()
+            .data;
+        assert_eq!(deployments.len(), 2);
+        assert_eq!(deployments[0].state, DeploymentState::Successful);
+        assert_eq!(deployments[0].environment.r#type, "production");
+        assert_eq!(
+            deployments[0].service_ids,
+            ["bfc5e3ab-0f24-4f5f-8d62-7f4e02a1d7d0"]
+        );
+        // Optional fields may be missing.
+        assert_eq!(deployments[1].state, DeploymentState::Started);
+        assert!(deployments[1].service_ids.is_empty());
+        assert_eq!(deployments[1].updated_at, None);
+    }
+}
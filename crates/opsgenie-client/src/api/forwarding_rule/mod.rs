@@ -0,0 +1,80 @@
+use serde::Serialize;
+
+use crate::api::{
+    response::{ApiResponse, NoData},
+    ApiVersion,
+};
+
+pub mod request;
+pub mod response;
+
+/// Type of the identifier used to look up a forwarding rule.
+#[derive(Debug, Default, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IdentifierType {
+    #[default]
+    Id,
+    Alias,
+}
+
+const VERSION: ApiVersion = ApiVersion::V2;
+
+#[derive(Debug)]
+pub struct ForwardingRuleApi<'a>(pub(crate) &'a crate::OpsgenieClient);
+
+impl<'a> ForwardingRuleApi<'a> {
+    pub async fn list(&self) -> crate::Result<ApiResponse<Vec<self::response::ForwardingRule>>> {
+        self.0.get(VERSION, "forwarding-rules", &()).await
+    }
+
+    pub async fn get(
+        &self,
+        identifier: &str,
+        identifier_type: IdentifierType,
+    ) -> crate::Result<ApiResponse<self::response::ForwardingRule>> {
+        self.0
+            .get(
+                VERSION,
+                &format!("forwarding-rules/{}", identifier),
+                &[("identifierType", identifier_type)],
+            )
+            .await
+    }
+
+    pub async fn create(
+        &self,
+        request: &self::request::ForwardingRule,
+    ) -> crate::Result<ApiResponse<self::response::ForwardingRuleRef>> {
+        self.0.post(VERSION, "forwarding-rules", &(), request).await
+    }
+
+    pub async fn update(
+        &self,
+        identifier: &str,
+        identifier_type: IdentifierType,
+        request: &self::request::ForwardingRule,
+    ) -> crate::Result<ApiResponse<self::response::ForwardingRuleRef>> {
+        self.0
+            .put(
+                VERSION,
+                &format!("forwarding-rules/{}", identifier),
+                &[("identifierType", identifier_type)],
+                request,
+            )
+            .await
+    }
+
+    pub async fn delete(
+        &self,
+        identifier: &str,
+        identifier_type: IdentifierType,
+    ) -> crate::Result<ApiResponse<NoData>> {
+        self.0
+            .delete(
+                VERSION,
+                &format!("forwarding-rules/{}", identifier),
+                &[("identifierType", identifier_type)],
+            )
+            .await
+    }
+}
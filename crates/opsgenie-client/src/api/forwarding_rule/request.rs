@@ -0,0 +1,41 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserRef {
+    pub username: String,
+}
+
+/// Used both to create and to update forwarding rules.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ForwardingRule {
+    pub from_user: UserRef,
+    pub to_user: UserRef,
+    pub start_date: DateTime<Utc>,
+    pub end_date: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+}
+
+impl ForwardingRule {
+    pub fn new(
+        from_username: impl Into<String>,
+        to_username: impl Into<String>,
+        start_date: DateTime<Utc>,
+        end_date: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            from_user: UserRef {
+                username: from_username.into(),
+            },
+            to_user: UserRef {
+                username: to_username.into(),
+            },
+            start_date,
+            end_date,
+            alias: None,
+        }
+    }
+}
@@ -0,0 +1,101 @@
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+
+use crate::api::team::response::User;
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ForwardingRule {
+    pub id: String,
+    pub alias: Option<String>,
+    pub from_user: User,
+    pub to_user: User,
+    pub start_date: DateTime<FixedOffset>,
+    pub end_date: DateTime<FixedOffset>,
+}
+
+impl ForwardingRule {
+    /// Checks whether the rule forwards notifications at the given moment.
+    pub fn is_active_at(&self, time: DateTime<FixedOffset>) -> bool {
+        self.start_date <= time && time < self.end_date
+    }
+}
+
+/// Returned by create and update requests.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ForwardingRuleRef {
+    pub id: String,
+    pub alias: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        api::forwarding_rule::{request, IdentifierType},
+        test_utils::replay_client,
+    };
+
+    #[tokio::test]
+    async fn create_forwarding_rule_response() {
+        let client = replay_client("forwarding_rule/create_forwarding_rule_response");
+        let start = DateTime::from_timestamp(1_499_241_600, 0).unwrap();
+        let end = DateTime::from_timestamp(1_499_364_000, 0).unwrap();
+        let request = request::ForwardingRule::new("neo", "trinity", start, end);
+        let response = client.forwarding_rule().create(&request).await.unwrap();
+        assert_eq!(response.data.id, "7c0a5a74-f1de-4c77-8b6b-a1d2e9c0e1b4");
+        assert_eq!(response.data.alias.as_deref(), Some("vacation"));
+    }
+
+    #[tokio::test]
+    async fn update_forwarding_rule_response() {
+        let client = replay_client("forwarding_rule/update_forwarding_rule_response");
+        let start = DateTime::from_timestamp(1_499_241_600, 0).unwrap();
+        let end = DateTime::from_timestamp(1_499_364_000, 0).unwrap();
+        let request = request::ForwardingRule::new("neo", "trinity", start, end);
+        let response = client
+            .forwarding_rule()
+            .update("vacation", IdentifierType::Alias, &request)
+            .await
+            .unwrap();
+        assert_eq!(response.data.alias.as_deref(), Some("vacation"));
+    }
+
+    #[tokio::test]
+    async fn list_forwarding_rules_response() {
+        let client = replay_client("forwarding_rule/list_forwarding_rules_response");
+        let rules = client.forwarding_rule().list().await.unwrap().data;
+        assert_eq!(rules.len(), 1);
+        assert_eq!(rules[0].alias.as_deref(), Some("vacation"));
+        assert_eq!(
+            rules[0].from_user.username.as_deref(),
+            Some("user@opsgenie.com")
+        );
+        assert_eq!(
+            rules[0].to_user.username.as_deref(),
+            Some("user2@opsgenie.com")
+        );
+        assert_eq!(
+            rules[0].start_date,
+            DateTime::parse_from_rfc3339("2017-07-05T08:00:00Z").unwrap()
+        );
+    }
+
+    #[test]
+    fn rule_is_active() {
+        let fixture = r#"{
+    "fromUser": { "username": "user@opsgenie.com" },
+    "toUser": { "username": "user2@opsgenie.com" },
+    "startDate": "2017-07-05T08:00:00Z",
+    "endDate": "2017-07-06T18:00:00Z",
+    "id": "7c0a5a74-f1de-4c77-8b6b-a1d2e9c0e1b4"
+}"#;
+        let rule: ForwardingRule = serde_json::from_str(fixture).unwrap();
+        let time = |s: &str| DateTime::parse_from_rfc3339(s).unwrap();
+        assert!(!rule.is_active_at(time("2017-07-05T07:59:59Z")));
+        assert!(rule.is_active_at(time("2017-07-05T08:00:00Z")));
+        assert!(rule.is_active_at(time("2017-07-06T17:59:59Z")));
+        assert!(!rule.is_active_at(time("2017-07-06T18:00:00Z")));
+    }
+}
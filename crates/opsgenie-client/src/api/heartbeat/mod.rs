@@ -0,0 +1,33 @@
+use crate::api::{
+    response::{ApiResponse, NoData},
+    ApiVersion,
+};
+
+const VERSION: ApiVersion = ApiVersion::V2;
+
+#[derive(Debug)]
+pub struct HeartbeatApi<'a>(pub(crate) &'a crate::OpsgenieClient);
+
+impl<'a> HeartbeatApi<'a> {
+    /// Notifies Opsgenie that the monitored system is alive.
+    pub async fn ping(&self, name: &str) -> crate::Result<ApiResponse<NoData>> {
+        self.0
+            .get(VERSION, &format!("heartbeats/{}/ping", name), &())
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::replay_client;
+
+    #[tokio::test]
+    async fn ping_response() {
+        let client = replay_client("heartbeat/ping_response");
+        let response = client.heartbeat().ping("HeartbeatName").await.unwrap();
+        assert_eq!(
+            response.result.as_deref(),
+            Some("PONG - Heartbeat received")
+        );
+    }
+}
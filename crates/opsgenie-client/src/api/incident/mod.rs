@@ -0,0 +1,60 @@
+use serde::Serialize;
+
+use crate::{
+    api::{response::ApiResponse, ApiVersion},
+    pagination::Pagination,
+    query_builder::ToFilter,
+};
+
+pub mod response;
+
+/// Type of the identifier used to look up an incident.
+#[derive(Debug, Default, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IdentifierType {
+    #[default]
+    Id,
+    Tiny,
+}
+
+const VERSION: ApiVersion = ApiVersion::V1;
+
+#[derive(Debug)]
+pub struct IncidentApi<'a>(pub(crate) &'a crate::OpsgenieClient);
+
+impl<'a> IncidentApi<'a> {
+    pub async fn get(
+        &self,
+        identifier: &str,
+        identifier_type: IdentifierType,
+    ) -> crate::Result<ApiResponse<self::response::Incident>> {
+        self.0
+            .get(
+                VERSION,
+                &format!("incidents/{}", identifier),
+                &[("identifierType", identifier_type)],
+            )
+            .await
+    }
+
+    /// Lists a page of the incidents matching the query.
+    /// Incidents can be sorted by `createdAt`, `updatedAt`, `tinyId`, etc.
+    pub async fn list(
+        &self,
+        query: impl ToFilter,
+        pagination: &Pagination,
+    ) -> crate::Result<ApiResponse<Vec<self::response::Incident>>> {
+        #[derive(Serialize)]
+        struct ListQuery<'a> {
+            query: String,
+            #[serde(flatten)]
+            pagination: &'a Pagination,
+        }
+
+        let query = query.to_filter();
+        tracing::debug!(query=%query, "Sending query");
+        self.0
+            .get(VERSION, "incidents", &ListQuery { query, pagination })
+            .await
+    }
+}
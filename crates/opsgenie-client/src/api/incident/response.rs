@@ -0,0 +1,84 @@
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+
+use crate::api::alert::response::Responder;
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Incident {
+    pub id: String,
+    pub tiny_id: String,
+    pub message: String,
+    pub description: Option<String>,
+    /// `open`, `resolved` or `closed`.
+    pub status: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub created_at: DateTime<FixedOffset>,
+    pub updated_at: DateTime<FixedOffset>,
+    pub priority: String,
+    /// ID of the team owning the incident.
+    pub owner_team: Option<String>,
+    #[serde(default)]
+    pub responders: Vec<Responder>,
+    /// IDs of the services impacted by the incident.
+    #[serde(default)]
+    pub impacted_services: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        api::incident::IdentifierType,
+        pagination::Pagination,
+        query_builder::{AlertStatus, Query},
+        test_utils::replay_client,
+    };
+
+    #[tokio::test]
+    async fn get_response() {
+        let client = replay_client("incident/get_response");
+        let incident = client
+            .incident()
+            .get("696f0409-a0c4-4fb3-9bb1-8e2b26f5e0a1", IdentifierType::Id)
+            .await
+            .unwrap()
+            .data;
+        assert_eq!(incident.tiny_id, "63");
+        assert_eq!(incident.status, "open");
+        assert_eq!(incident.priority, "P1");
+        assert_eq!(
+            incident.owner_team.as_deref(),
+            Some("eb24816b-5969-4b2a-8d9e-1d06ab2b8a7e")
+        );
+        assert_eq!(
+            incident.impacted_services,
+            ["bfc5e3ab-0f24-4f5f-8d62-7f4e02a1d7d0"]
+        );
+        assert_eq!(incident.responders.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn list_response() {
+        let client = replay_client("incident/list_response");
+        let incidents = client
+            .incident()
+            .list(
+                Query::status(AlertStatus::Open),
+                &Pagination::new().with_max_limit(),
+            )
+            .await
+            .unwrap()
+            .data;
+        assert_eq!(incidents.len(), 2);
+        assert_eq!(incidents[0].message, "Checkout is down");
+        assert_eq!(incidents[0].tags, ["checkout"]);
+        assert_eq!(
+            incidents[0].impacted_services,
+            ["bfc5e3ab-0f24-4f5f-8d62-7f4e02a1d7d0"]
+        );
+        // Optional fields may be missing.
+        assert!(incidents[1].impacted_services.is_empty());
+        assert_eq!(incidents[1].owner_team, None);
+    }
+}
@@ -1,7 +1,37 @@
-pub use self::{alert::AlertApi, on_call::OnCallApi, schedule::ScheduleApi, team::TeamApi};
+pub use self::{
+    account::AccountApi, alert::AlertApi, audit_log::AuditLogApi,
+    custom_user_role::CustomUserRoleApi, deployment::DeploymentApi,
+    forwarding_rule::ForwardingRuleApi, heartbeat::HeartbeatApi, incident::IncidentApi,
+    on_call::OnCallApi, schedule::ScheduleApi, service::ServiceApi, team::TeamApi,
+};
 
+pub mod account;
 pub mod alert;
+pub mod audit_log;
+pub mod custom_user_role;
+pub mod deployment;
+pub mod forwarding_rule;
+pub mod heartbeat;
+pub mod incident;
 pub mod on_call;
 pub mod response;
 pub mod schedule;
+pub mod service;
 pub mod team;
+
+/// Version of the Opsgenie API. Each API group declares the version it belongs to,
+/// since some endpoints are only available in v1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ApiVersion {
+    V1,
+    V2,
+}
+
+impl ApiVersion {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApiVersion::V1 => "v1",
+            ApiVersion::V2 => "v2",
+        }
+    }
+}
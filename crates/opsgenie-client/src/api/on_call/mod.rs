@@ -1,7 +1,9 @@
-use crate::api::response::ApiResponse;
+use crate::api::{response::ApiResponse, ApiVersion};
 
 pub mod response;
 
+const VERSION: ApiVersion = ApiVersion::V2;
+
 #[derive(Debug)]
 pub struct OnCallApi<'a>(pub(crate) &'a crate::OpsgenieClient);
 
@@ -12,6 +14,7 @@ impl<'a> OnCallApi<'a> {
     ) -> crate::Result<ApiResponse<self::response::OnCallRecipients>> {
         self.0
             .get(
+                VERSION,
                 &format!("schedules/{}/on-calls", schedule_id),
                 &[("flat", true)],
             )
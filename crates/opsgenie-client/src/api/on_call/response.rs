@@ -1,6 +1,6 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct OnCallRecipients {
     pub on_call_recipients: Vec<String>,
@@ -8,27 +8,16 @@ pub struct OnCallRecipients {
 
 #[cfg(test)]
 mod tests {
-    use super::*;
-    use crate::test_utils::response_test;
+    use crate::test_utils::replay_client;
 
-    #[test]
-    fn whoisoncall_flat_response() {
-        let fixture = r#"
-        {
-    "data": {
-        "_parent": {
-            "id": "d875alp4-9b4e-4219-a803-0c26936d18de",
-            "name": "ScheduleName",
-            "enabled": true
-        },
-        "onCallRecipients": [
-            "user4@opsgenie.com"
-        ]
-    },
-    "took": 0.101,
-    "requestId": "7f0alpde-3c67-455f-97ec-24754432d413"
-}
-        "#;
-        response_test::<OnCallRecipients>(fixture);
+    #[tokio::test]
+    async fn whoisoncall_flat_response() {
+        let client = replay_client("on_call/whoisoncall_flat_response");
+        let response = client
+            .on_call()
+            .whoisoncall("8418d193-2dab-4490-b331-8c02cdd196b7")
+            .await
+            .unwrap();
+        assert_eq!(response.data.on_call_recipients, ["user4@opsgenie.com"]);
     }
 }
@@ -17,16 +17,27 @@ pub struct ApiResponse<T> {
     pub took: f64,
     pub expandable: Option<serde_json::Value>,
     pub message: Option<String>,
+    /// Used for marker-based pagination, e.g. when listing audit log files.
+    pub marker: Option<String>,
     pub request_id: RequestId,
 }
 
+/// Data for the responses that have no payload, e.g. for deletion requests.
+/// Opsgenie omits the `data` field in such responses.
+pub type NoData = Option<serde_json::Value>;
+
 #[derive(Debug, Clone, Deserialize, thiserror::Error)]
 #[serde(rename_all = "camelCase")]
 pub struct ApiError {
     pub request_id: RequestId,
     pub message: String,
     pub took: f64,
+    /// Not present for some errors, e.g. authentication failures.
+    #[serde(default)]
     pub errors: HashMap<String, String>,
+    /// Status of the response. Not part of the response body.
+    #[serde(skip)]
+    pub status: Option<http::StatusCode>,
 }
 
 impl fmt::Display for ApiError {
@@ -34,3 +45,20 @@ impl fmt::Display for ApiError {
         self.message.fmt(f)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn authentication_error() {
+        let fixture = r#"{
+    "message": "Could not authenticate",
+    "took": 0.001,
+    "requestId": "a1c1f3e2-5b1d-4f6e-9b0a-3c2d1e0f9a8b"
+}
+"#;
+        let error: ApiError = serde_json::from_str(fixture).expect("Unable to deserialize");
+        assert_eq!(error.to_string(), "Could not authenticate");
+    }
+}
@@ -1,7 +1,9 @@
-use crate::api::response::ApiResponse;
+use crate::api::{response::ApiResponse, ApiVersion};
 
 pub mod response;
 
+const VERSION: ApiVersion = ApiVersion::V2;
+
 #[derive(Debug)]
 pub struct ScheduleApi<'a>(pub(crate) &'a crate::OpsgenieClient);
 
@@ -9,6 +11,6 @@ impl<'a> ScheduleApi<'a> {
     pub async fn list_schedules(
         &self,
     ) -> crate::Result<ApiResponse<Vec<self::response::Schedule>>> {
-        self.0.get("schedules", &()).await
+        self.0.get(VERSION, "schedules", &()).await
     }
 }
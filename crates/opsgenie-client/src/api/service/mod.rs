@@ -0,0 +1,144 @@
+use crate::{
+    api::{
+        response::{ApiResponse, NoData},
+        ApiVersion,
+    },
+    pagination::Pagination,
+};
+
+pub mod request;
+pub mod response;
+
+/// Services API is only available in v1 of the Opsgenie API.
+const VERSION: ApiVersion = ApiVersion::V1;
+
+const SERVICES_PATH: &str = "services";
+
+#[derive(Debug)]
+pub struct ServiceApi<'a>(pub(crate) &'a crate::OpsgenieClient);
+
+impl<'a> ServiceApi<'a> {
+    pub async fn list(
+        &self,
+        pagination: &Pagination,
+    ) -> crate::Result<ApiResponse<Vec<self::response::Service>>> {
+        self.0.get(VERSION, SERVICES_PATH, pagination).await
+    }
+
+    pub async fn get(
+        &self,
+        service_id: &str,
+    ) -> crate::Result<ApiResponse<self::response::Service>> {
+        self.0
+            .get(VERSION, &format!("{SERVICES_PATH}/{service_id}"), &())
+            .await
+    }
+
+    pub async fn create(
+        &self,
+        request: &self::request::CreateService,
+    ) -> crate::Result<ApiResponse<self::response::ServiceRef>> {
+        self.0.post(VERSION, SERVICES_PATH, &(), request).await
+    }
+
+    pub async fn update(
+        &self,
+        service_id: &str,
+        request: &self::request::UpdateService,
+    ) -> crate::Result<ApiResponse<self::response::ServiceRef>> {
+        self.0
+            .patch(VERSION, &format!("{SERVICES_PATH}/{service_id}"), request)
+            .await
+    }
+
+    pub async fn delete(&self, service_id: &str) -> crate::Result<ApiResponse<NoData>> {
+        self.0
+            .delete(VERSION, &format!("{SERVICES_PATH}/{service_id}"), &())
+            .await
+    }
+
+    pub async fn list_incident_rules(
+        &self,
+        service_id: &str,
+    ) -> crate::Result<ApiResponse<self::response::IncidentRules>> {
+        self.0
+            .get(
+                VERSION,
+                &format!("{SERVICES_PATH}/{service_id}/incident-rules"),
+                &(),
+            )
+            .await
+    }
+
+    pub async fn create_incident_rule(
+        &self,
+        service_id: &str,
+        request: &self::request::IncidentRule,
+    ) -> crate::Result<ApiResponse<self::response::IncidentRuleRef>> {
+        self.0
+            .post(
+                VERSION,
+                &format!("{SERVICES_PATH}/{service_id}/incident-rules"),
+                &(),
+                request,
+            )
+            .await
+    }
+
+    pub async fn update_incident_rule(
+        &self,
+        service_id: &str,
+        incident_rule_id: &str,
+        request: &self::request::IncidentRule,
+    ) -> crate::Result<ApiResponse<self::response::IncidentRuleRef>> {
+        self.0
+            .put(
+                VERSION,
+                &format!("{SERVICES_PATH}/{service_id}/incident-rules/{incident_rule_id}"),
+                &(),
+                request,
+            )
+            .await
+    }
+
+    pub async fn delete_incident_rule(
+        &self,
+        service_id: &str,
+        incident_rule_id: &str,
+    ) -> crate::Result<ApiResponse<NoData>> {
+        self.0
+            .delete(
+                VERSION,
+                &format!("{SERVICES_PATH}/{service_id}/incident-rules/{incident_rule_id}"),
+                &(),
+            )
+            .await
+    }
+
+    pub async fn get_audience_template(
+        &self,
+        service_id: &str,
+    ) -> crate::Result<ApiResponse<self::response::AudienceTemplate>> {
+        self.0
+            .get(
+                VERSION,
+                &format!("{SERVICES_PATH}/{service_id}/audience-templates"),
+                &(),
+            )
+            .await
+    }
+
+    pub async fn update_audience_template(
+        &self,
+        service_id: &str,
+        request: &self::response::AudienceTemplate,
+    ) -> crate::Result<ApiResponse<NoData>> {
+        self.0
+            .patch(
+                VERSION,
+                &format!("{SERVICES_PATH}/{service_id}/audience-templates"),
+                request,
+            )
+            .await
+    }
+}
@@ -0,0 +1,50 @@
+use serde::Serialize;
+
+use super::response::{Condition, ConditionMatchType, IncidentProperties, Visibility};
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateService {
+    pub name: String,
+    pub team_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub visibility: Option<Visibility>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+impl CreateService {
+    pub fn new(name: impl Into<String>, team_id: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            team_id: team_id.into(),
+            description: None,
+            visibility: None,
+            tags: Vec::new(),
+        }
+    }
+}
+
+/// Only the provided fields are updated.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateService {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub visibility: Option<Visibility>,
+}
+
+/// Used both to create and to update incident rules.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IncidentRule {
+    pub condition_match_type: ConditionMatchType,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub conditions: Vec<Condition>,
+    pub incident_properties: IncidentProperties,
+}
@@ -0,0 +1,238 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Visibility {
+    TeamMembers,
+    OpsgenieUsers,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Links {
+    pub web: Option<String>,
+    pub api: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Service {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub team_id: String,
+    pub visibility: Option<Visibility>,
+    pub tags: Option<Vec<String>>,
+    pub links: Option<Links>,
+    pub is_external: Option<bool>,
+}
+
+/// Returned by create and update requests.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceRef {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ConditionMatchType {
+    MatchAll,
+    MatchAnyCondition,
+    MatchAllConditions,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Condition {
+    pub field: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    #[serde(default)]
+    pub not: bool,
+    pub operation: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected_value: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StakeholderProperties {
+    pub enable: Option<bool>,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IncidentProperties {
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub details: HashMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub priority: String,
+    pub stakeholder_properties: StakeholderProperties,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IncidentRule {
+    pub id: String,
+    pub condition_match_type: ConditionMatchType,
+    #[serde(default)]
+    pub conditions: Vec<Condition>,
+    pub incident_properties: IncidentProperties,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IncidentRules {
+    pub incident_rules: Vec<IncidentRule>,
+}
+
+/// Returned by create and update requests.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IncidentRuleRef {
+    pub id: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AudienceResponders {
+    #[serde(default)]
+    pub teams: Vec<String>,
+    #[serde(default)]
+    pub individuals: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AudienceCondition {
+    pub match_field: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AudienceStakeholders {
+    #[serde(default)]
+    pub individuals: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub condition_match_type: Option<ConditionMatchType>,
+    #[serde(default)]
+    pub conditions: Vec<AudienceCondition>,
+}
+
+/// Audience template is used both in requests and in responses.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AudienceTemplate {
+    pub responders: AudienceResponders,
+    pub stakeholders: AudienceStakeholders,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{pagination::Pagination, test_utils::replay_client};
+
+    #[tokio::test]
+    async fn get_service_response() {
+        let client = replay_client("service/get_service_response");
+        let service = client
+            .service()
+            .get("bfc5e3ab-0f24-4f5f-8d62-7f4e02a1d7d0")
+            .await
+            .unwrap()
+            .data;
+        assert_eq!(service.name, "Service API Test Service");
+        assert_eq!(service.team_id, "eb24816b-5969-4b2a-8d9e-1d06ab2b8a7e");
+        assert_eq!(service.visibility, Some(Visibility::OpsgenieUsers));
+        assert_eq!(service.tags.as_deref(), Some(&["tag1".to_owned()][..]));
+        assert_eq!(service.is_external, Some(false));
+    }
+
+    #[tokio::test]
+    async fn list_services_response() {
+        let client = replay_client("service/list_services_response");
+        let services = client
+            .service()
+            .list(&Pagination::new())
+            .await
+            .unwrap()
+            .data;
+        assert_eq!(services.len(), 1);
+        assert_eq!(services[0].id, "bfc5e3ab-0f24-4f5f-8d62-7f4e02a1d7d0");
+        assert_eq!(services[0].visibility, Some(Visibility::TeamMembers));
+        assert!(services[0].links.is_none());
+    }
+
+    #[tokio::test]
+    async fn delete_service_response() {
+        let client = replay_client("service/delete_service_response");
+        let response = client
+            .service()
+            .delete("8418d193-2dab-4490-b331-8c02cdd196b7")
+            .await
+            .unwrap();
+        assert_eq!(response.result.as_deref(), Some("Deleted"));
+    }
+
+    #[tokio::test]
+    async fn list_incident_rules_response() {
+        let client = replay_client("service/list_incident_rules_response");
+        let rules = client
+            .service()
+            .list_incident_rules("8418d193-2dab-4490-b331-8c02cdd196b7")
+            .await
+            .unwrap()
+            .data
+            .incident_rules;
+        assert_eq!(rules.len(), 1);
+        let rule = &rules[0];
+        assert!(matches!(
+            rule.condition_match_type,
+            ConditionMatchType::MatchAllConditions
+        ));
+        assert_eq!(rule.conditions.len(), 2);
+        assert_eq!(rule.conditions[1].key.as_deref(), Some("region"));
+        assert!(rule.conditions[1].not);
+        assert_eq!(rule.incident_properties.priority, "P1");
+        assert_eq!(
+            rule.incident_properties.details["runbook"],
+            "https://example.com"
+        );
+    }
+
+    #[tokio::test]
+    async fn get_audience_template_response() {
+        let client = replay_client("service/get_audience_template_response");
+        let template = client
+            .service()
+            .get_audience_template("8418d193-2dab-4490-b331-8c02cdd196b7")
+            .await
+            .unwrap()
+            .data;
+        assert_eq!(
+            template.responders.teams,
+            ["eb24816b-5969-4b2a-8d9e-1d06ab2b8a7e"]
+        );
+        assert!(template.responders.individuals.is_empty());
+        assert!(matches!(
+            template.stakeholders.condition_match_type,
+            Some(ConditionMatchType::MatchAnyCondition)
+        ));
+        assert_eq!(template.stakeholders.conditions[0].match_field, "country");
+        assert_eq!(template.stakeholders.conditions[0].value, "Turkey");
+    }
+}
@@ -1,7 +1,9 @@
-use crate::api::response::ApiResponse;
+use crate::api::{response::ApiResponse, ApiVersion};
 
 pub mod response;
 
+const VERSION: ApiVersion = ApiVersion::V2;
+
 #[derive(Debug)]
 pub struct TeamApi<'a>(pub(crate) &'a crate::OpsgenieClient);
 
@@ -9,10 +11,12 @@ impl<'a> TeamApi<'a> {
     pub async fn list_teams(
         &self,
     ) -> crate::Result<ApiResponse<Vec<self::response::TeamDescriptor>>> {
-        self.0.get("teams", &()).await
+        self.0.get(VERSION, "teams", &()).await
     }
 
     pub async fn get(&self, team_id: String) -> crate::Result<ApiResponse<self::response::Team>> {
-        self.0.get(&format!("teams/{}", team_id), &()).await
+        self.0
+            .get(VERSION, &format!("teams/{}", team_id), &())
+            .await
     }
 }
@@ -1,20 +1,20 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct User {
     pub id: Option<String>,
     pub username: Option<String>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TeamMember {
     pub user: User,
     pub role: String,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TeamDescriptor {
     pub id: String,
@@ -22,7 +22,7 @@ pub struct TeamDescriptor {
     pub name: String,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Team {
     pub id: String,
@@ -33,72 +33,40 @@ pub struct Team {
 
 #[cfg(test)]
 mod tests {
-    use super::*;
-    use crate::test_utils::response_test;
+    use crate::test_utils::replay_client;
 
-    #[test]
-    fn get_team_response() {
-        let fixture = r#"{
-    "data": {
-        "id": "a30alp45-65bf-422f-9d41-67b10a67282a",
-        "name": "TeamName",
-        "description": "Team Description",
-        "members": [
-            {
-                "user": {
-                    "id": "a9514028-2bca-4510-a51f-4b65f2c33alp",
-                    "username": "user@opsgenie.com"
-                },
-                "role": "admin"
-            },
-            {
-                "user": {
-                    "id": "00564944-b42f-4b95-a882-ee9a5aalp9bb",
-                    "username": "user2@opsgenie.com"
-                },
-                "role": "user"
-            },
-            {
-                "user": {
-                    "id": "1f281991-bca3-4ae2-bdea-b02e94dalp53",
-                    "username": "user3@opsgenie.com"
-                },
-                "role": "user"
-            }
-        ]
-    },
-    "took": 0.021,
-    "requestId": "36d5c8c5-alpf-47b2-9964-9fd435e5e306"
-}
-"#;
-        response_test::<Team>(fixture);
+    #[tokio::test]
+    async fn get_team_response() {
+        let client = replay_client("team/get_team_response");
+        let team = client
+            .team()
+            .get("a30alp45-65bf-422f-9d41-67b10a67282a".into())
+            .await
+            .unwrap()
+            .data;
+        assert_eq!(team.name, "TeamName");
+        let members = team.members.unwrap();
+        let usernames: Vec<_> = members
+            .iter()
+            .map(|member| member.user.username.as_deref().unwrap())
+            .collect();
+        assert_eq!(
+            usernames,
+            [
+                "user@opsgenie.com",
+                "user2@opsgenie.com",
+                "user3@opsgenie.com"
+            ]
+        );
+        assert_eq!(members[0].role, "admin");
     }
 
-    #[test]
-    fn list_teams_response() {
-        let fixture = r#"
-        {
-    "data": [
-        {
-            "id": "90098alp9-f0e3-41d3-a060-0ea895027630",
-            "name": "ops_team",
-            "description": ""
-        },
-        {
-            "id": "a30alp45-65bf-422f-9d41-67b10a67282a",
-            "name": "TeamName2",
-            "description": "Description"
-        },
-        {
-            "id": "c569c016-alp9-4e20-8a28-bd5dc33b798e",
-            "name": "TeamName",
-            "description": ""
-        }
-    ],
-    "took": 1.08,
-    "requestId": "9cbfalp7-53f5-41ef-a360-be01277a903d"
-}
-"#;
-        response_test::<Vec<Team>>(fixture);
+    #[tokio::test]
+    async fn list_teams_response() {
+        let client = replay_client("team/list_teams_response");
+        let teams = client.team().list_teams().await.unwrap().data;
+        let names: Vec<_> = teams.iter().map(|team| team.name.as_str()).collect();
+        assert_eq!(names, ["ops_team", "TeamName2", "TeamName"]);
+        assert_eq!(teams[1].id, "a30alp45-65bf-422f-9d41-67b10a67282a");
     }
 }
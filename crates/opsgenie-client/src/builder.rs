@@ -0,0 +1,251 @@
+use std::{sync::Arc, time::Duration};
+
+use reqwest::{header::HeaderMap, tls, Certificate, Proxy};
+use url::Url;
+
+use crate::{transport::ReqwestTransport, ClientError, OpsgenieClient};
+
+/// User agent sent by default.
+const DEFAULT_USER_AGENT: &str = concat!("opsgenie-client/", env!("CARGO_PKG_VERSION"));
+
+/// Opsgenie instance to connect to.
+///
+/// [Corresponding API page](https://docs.opsgenie.com/docs/european-service-region)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Region {
+    #[default]
+    Us,
+    Eu,
+    /// Custom base URL, e.g. for a proxy or a mock server.
+    Custom(Url),
+}
+
+impl Region {
+    pub fn base_url(&self) -> Url {
+        match self {
+            Region::Us => "https://api.opsgenie.com/".parse().unwrap(),
+            Region::Eu => "https://api.eu.opsgenie.com/".parse().unwrap(),
+            Region::Custom(url) => url.clone(),
+        }
+    }
+}
+
+/// Builder for [`OpsgenieClient`] with custom connection settings.
+///
+/// ```
+/// use std::time::Duration;
+/// use opsgenie_client::{OpsgenieClient, Region};
+///
+/// let client = OpsgenieClient::builder("api-key")
+///     .region(Region::Eu)
+///     .connect_timeout(Duration::from_secs(5))
+///     .timeout(Duration::from_secs(30))
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Debug)]
+pub struct OpsgenieClientBuilder {
+    api_key: String,
+    region: Region,
+    http: reqwest::ClientBuilder,
+    /// Overrides all the HTTP settings if set.
+    http_client: Option<reqwest::Client>,
+}
+
+impl OpsgenieClientBuilder {
+    pub(crate) fn new(api_key: String) -> Self {
+        Self {
+            api_key,
+            region: Region::default(),
+            http: reqwest::Client::builder().user_agent(DEFAULT_USER_AGENT),
+            http_client: None,
+        }
+    }
+
+    /// Sets the Opsgenie region. Defaults to [`Region::Us`].
+    pub fn region(mut self, region: Region) -> Self {
+        self.region = region;
+        self
+    }
+
+    /// Sets a custom base URL, e.g. `https://api.opsgenie.com`.
+    pub fn base_url(self, base_url: Url) -> Self {
+        self.region(Region::Custom(base_url))
+    }
+
+    /// Sets the timeout for establishing connections.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.http = self.http.connect_timeout(timeout);
+        self
+    }
+
+    /// Sets the timeout for each read from the connection.
+    pub fn read_timeout(mut self, timeout: Duration) -> Self {
+        self.http = self.http.read_timeout(timeout);
+        self
+    }
+
+    /// Sets the timeout for the whole request, from connecting until the response body is read.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.http = self.http.timeout(timeout);
+        self
+    }
+
+    /// Sends the requests through the proxy. May be called multiple times.
+    pub fn proxy(mut self, proxy: Proxy) -> Self {
+        self.http = self.http.proxy(proxy);
+        self
+    }
+
+    /// Sets the `User-Agent` header. Defaults to `opsgenie-client/<version>`.
+    pub fn user_agent(mut self, user_agent: &str) -> Self {
+        self.http = self.http.user_agent(user_agent);
+        self
+    }
+
+    /// Sets headers sent with every request.
+    pub fn default_headers(mut self, headers: HeaderMap) -> Self {
+        self.http = self.http.default_headers(headers);
+        self
+    }
+
+    /// Trusts an additional root certificate, e.g. of a corporate proxy.
+    pub fn add_root_certificate(mut self, certificate: Certificate) -> Self {
+        self.http = self.http.add_root_certificate(certificate);
+        self
+    }
+
+    /// Sets the minimum accepted TLS version.
+    pub fn min_tls_version(mut self, version: tls::Version) -> Self {
+        self.http = self.http.min_tls_version(version);
+        self
+    }
+
+    /// Disables the validation of server certificates.
+    ///
+    /// This is dangerous and should only be used for testing.
+    pub fn danger_accept_invalid_certs(mut self, accept: bool) -> Self {
+        self.http = self.http.danger_accept_invalid_certs(accept);
+        self
+    }
+
+    /// Uses a pre-configured HTTP client. The other HTTP settings of the builder are ignored.
+    pub fn http_client(mut self, client: reqwest::Client) -> Self {
+        self.http_client = Some(client);
+        self
+    }
+
+    /// Creates the client, validating the base URL.
+    pub fn build(self) -> crate::Result<OpsgenieClient> {
+        let base_url = validate_base_url(self.region.base_url())?;
+        let client = match self.http_client {
+            Some(client) => client,
+            None => self.http.build()?,
+        };
+        Ok(OpsgenieClient {
+            base_url,
+            api_key: self.api_key,
+            transport: Arc::new(ReqwestTransport::new(client.clone())),
+            client,
+            metrics_hook: None,
+        })
+    }
+}
+
+/// Checks that the API paths can be appended to the URL.
+fn validate_base_url(mut url: Url) -> crate::Result<Url> {
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(ClientError::Config(format!(
+            "base URL must use HTTP or HTTPS: {url}"
+        )));
+    }
+    if url.query().is_some() || url.fragment().is_some() {
+        return Err(ClientError::Config(format!(
+            "base URL must not have a query or fragment: {url}"
+        )));
+    }
+    // Otherwise, the last path segment would be replaced when joining paths.
+    if !url.path().ends_with('/') {
+        let path = format!("{}/", url.path());
+        url.set_path(&path);
+    }
+    Ok(url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::ApiVersion;
+
+    /// Returns the URL of the alerts endpoint for the base URL.
+    fn alerts_url(base_url: &str) -> crate::Result<Url> {
+        let client = OpsgenieClient::builder("key")
+            .base_url(base_url.parse().unwrap())
+            .build()?;
+        Ok(client.url(ApiVersion::V2, "alerts"))
+    }
+
+    #[test]
+    fn regions() {
+        let client = OpsgenieClient::builder("key").build().unwrap();
+        assert_eq!(
+            client.url(ApiVersion::V2, "alerts").as_str(),
+            "https://api.opsgenie.com/v2/alerts"
+        );
+        let client = OpsgenieClient::builder("key")
+            .region(Region::Eu)
+            .build()
+            .unwrap();
+        assert_eq!(
+            client.url(ApiVersion::V1, "services").as_str(),
+            "https://api.eu.opsgenie.com/v1/services"
+        );
+    }
+
+    #[test]
+    fn custom_base_urls() {
+        assert_eq!(
+            alerts_url("http://localhost:8080").unwrap().as_str(),
+            "http://localhost:8080/v2/alerts"
+        );
+        assert_eq!(
+            alerts_url("https://proxy.example.com/opsgenie")
+                .unwrap()
+                .as_str(),
+            "https://proxy.example.com/opsgenie/v2/alerts"
+        );
+        assert_eq!(
+            alerts_url("https://proxy.example.com/opsgenie/")
+                .unwrap()
+                .as_str(),
+            "https://proxy.example.com/opsgenie/v2/alerts"
+        );
+        let err = alerts_url("ftp://example.com").unwrap_err();
+        assert!(matches!(err, ClientError::Config(_)), "{err:?}");
+        let err = alerts_url("https://example.com/?key=value").unwrap_err();
+        assert!(matches!(err, ClientError::Config(_)), "{err:?}");
+        let err = alerts_url("mailto:ops@example.com").unwrap_err();
+        assert!(matches!(err, ClientError::Config(_)), "{err:?}");
+
+        // The constructors report invalid URLs rather than panicking.
+        let url: Url = "ftp://example.com".parse().unwrap();
+        let err = OpsgenieClient::new(url.clone(), "key".into()).unwrap_err();
+        assert!(matches!(err, ClientError::Config(_)), "{err:?}");
+        let err = OpsgenieClient::with_http_client(url, "key".into(), reqwest::Client::new())
+            .unwrap_err();
+        assert!(matches!(err, ClientError::Config(_)), "{err:?}");
+    }
+
+    #[test]
+    fn http_settings() {
+        OpsgenieClient::builder("key")
+            .connect_timeout(Duration::from_secs(1))
+            .read_timeout(Duration::from_secs(2))
+            .timeout(Duration::from_secs(3))
+            .proxy(Proxy::https("http://proxy.example.com:3128").unwrap())
+            .user_agent("exporter/1.0")
+            .min_tls_version(tls::Version::TLS_1_2)
+            .build()
+            .unwrap();
+    }
+}
@@ -0,0 +1,442 @@
+//! Recording and replaying of HTTP interactions.
+//!
+//! A cassette is a JSON file with the requests sent by the client and the responses received
+//! from Opsgenie. Interactions are recorded once against the real API, and then replayed in
+//! tests without network access:
+//!
+//! ```no_run
+//! # async fn example() -> opsgenie_client::Result<()> {
+//! use opsgenie_client::{cassette::Cassette, OpsgenieClient};
+//!
+//! let url = "https://api.opsgenie.com".parse()?;
+//! let client = OpsgenieClient::new(url, "api-key".into())?
+//!     .with_cassette(Cassette::replay("cassettes/teams.json")?);
+//! let teams = client.team().list_teams().await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The API key is redacted from the recorded requests: the `Authorization` header, the `apiKey`
+//! and `GenieKey` query parameters, and `apiKey` fields in request bodies. Response bodies that
+//! are not valid JSON are stored as text.
+//!
+//! A replayed cassette panics when it is dropped with interactions that were never replayed, so
+//! tests fail when the client stops sending a recorded request.
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    transport::{BoxFuture, Transport},
+    ClientError,
+};
+
+/// Replaces the API key in the recorded `Authorization` header.
+const REDACTED: &str = "GenieKey [REDACTED]";
+/// Replaces the API key in query parameters and request bodies.
+const REDACTED_KEY: &str = "[REDACTED]";
+/// Query parameters and body fields holding the API key, compared case-insensitively.
+const SECRET_NAMES: &[&str] = &["apiKey", "GenieKey"];
+
+fn is_secret(name: &str) -> bool {
+    SECRET_NAMES
+        .iter()
+        .any(|secret| secret.eq_ignore_ascii_case(name))
+}
+
+/// Replaces the values of the secret query parameters, keeping the rest of the query as sent.
+fn redact_query(query: &str) -> String {
+    query
+        .split('&')
+        .map(|pair| match pair.split_once('=') {
+            Some((name, _)) if is_secret(name) => format!("{name}={REDACTED_KEY}"),
+            _ => pair.to_owned(),
+        })
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+/// Replaces the values of the secret fields, at any depth of the body.
+fn redact_body(body: &mut serde_json::Value) {
+    match body {
+        serde_json::Value::Object(fields) => {
+            for (name, value) in fields {
+                if is_secret(name) {
+                    *value = serde_json::Value::String(REDACTED_KEY.to_owned());
+                } else {
+                    redact_body(value);
+                }
+            }
+        }
+        serde_json::Value::Array(values) => values.iter_mut().for_each(redact_body),
+        _ => {}
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordedRequest {
+    pub method: String,
+    /// Path of the URL, including the API version, e.g. `/v2/alerts`.
+    pub path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub query: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<serde_json::Value>,
+}
+
+impl RecordedRequest {
+    fn new(request: &reqwest::Request) -> Self {
+        let headers = request
+            .headers()
+            .iter()
+            .map(|(name, value)| {
+                let value = if name == http::header::AUTHORIZATION {
+                    REDACTED.to_string()
+                } else {
+                    String::from_utf8_lossy(value.as_bytes()).into_owned()
+                };
+                (name.to_string(), value)
+            })
+            .collect();
+        let body = request
+            .body()
+            .and_then(reqwest::Body::as_bytes)
+            .and_then(|body| serde_json::from_slice(body).ok())
+            .map(|mut body| {
+                redact_body(&mut body);
+                body
+            });
+        Self {
+            method: request.method().to_string(),
+            path: request.url().path().to_string(),
+            query: request.url().query().map(redact_query),
+            headers,
+            body,
+        }
+    }
+
+    /// Requests are matched by their method and URL.
+    fn matches(&self, other: &Self) -> bool {
+        self.method == other.method && self.path == other.path && self.query == other.query
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordedResponse {
+    pub status: u16,
+    /// Body of the response, if it is valid JSON.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<serde_json::Value>,
+    /// Body of the response, if it is not valid JSON.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+}
+
+impl RecordedResponse {
+    fn new(status: u16, body: &[u8]) -> Self {
+        match serde_json::from_slice(body) {
+            Ok(body) => Self {
+                status,
+                body: Some(body),
+                text: None,
+            },
+            Err(_) => Self {
+                status,
+                body: None,
+                text: Some(String::from_utf8_lossy(body).into_owned()),
+            },
+        }
+    }
+
+    fn to_response(&self) -> reqwest::Response {
+        let body = match (&self.body, &self.text) {
+            (Some(body), _) => body.to_string(),
+            (None, Some(text)) => text.clone(),
+            (None, None) => String::new(),
+        };
+        let response = http::Response::builder()
+            .status(self.status)
+            .body(body)
+            .expect("Invalid recorded response");
+        reqwest::Response::from(response)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Interaction {
+    pub request: RecordedRequest,
+    pub response: RecordedResponse,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CassetteFile {
+    interactions: Vec<Interaction>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CassetteMode {
+    /// Requests are sent to Opsgenie, and the interactions are saved to the cassette.
+    Record,
+    /// Responses are served from the cassette, no requests are sent.
+    Replay,
+}
+
+#[derive(Debug)]
+struct Tape {
+    interactions: Vec<Interaction>,
+    /// Whether each interaction was already replayed.
+    played: Vec<bool>,
+}
+
+/// Stores HTTP interactions in a JSON file.
+#[derive(Debug)]
+pub struct Cassette {
+    path: PathBuf,
+    mode: CassetteMode,
+    tape: Mutex<Tape>,
+}
+
+impl Cassette {
+    /// Creates a cassette recording to the file.
+    /// The file is overwritten after each request.
+    pub fn record(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            mode: CassetteMode::Record,
+            tape: Mutex::new(Tape {
+                interactions: Vec::new(),
+                played: Vec::new(),
+            }),
+        }
+    }
+
+    /// Loads the interactions recorded to the file.
+    pub fn replay(path: impl Into<PathBuf>) -> crate::Result<Self> {
+        let path = path.into();
+        let file = std::fs::read(&path)?;
+        let file: CassetteFile = serde_json::from_slice(&file).map_err(std::io::Error::from)?;
+        Ok(Self {
+            path,
+            mode: CassetteMode::Replay,
+            tape: Mutex::new(Tape {
+                played: vec![false; file.interactions.len()],
+                interactions: file.interactions,
+            }),
+        })
+    }
+
+    pub fn mode(&self) -> CassetteMode {
+        self.mode
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the interactions recorded or loaded so far.
+    pub fn interactions(&self) -> Vec<Interaction> {
+        self.tape.lock().unwrap().interactions.clone()
+    }
+
+    async fn record_request(
+        &self,
+        transport: &dyn Transport,
+        request: reqwest::Request,
+    ) -> crate::Result<reqwest::Response> {
+        let recorded_request = RecordedRequest::new(&request);
+        let response = transport.execute(request).await?;
+        let status = response.status().as_u16();
+        let body = response.bytes().await?;
+        let recorded_response = RecordedResponse::new(status, &body);
+
+        let file = {
+            let mut tape = self.tape.lock().unwrap();
+            tape.interactions.push(Interaction {
+                request: recorded_request,
+                response: recorded_response.clone(),
+            });
+            tape.played.push(false);
+            serde_json::to_vec_pretty(&CassetteFile {
+                interactions: tape.interactions.clone(),
+            })
+            .map_err(std::io::Error::from)?
+        };
+        if let Some(parent) = self.path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(&self.path, file).await?;
+
+        Ok(recorded_response.to_response())
+    }
+
+    /// Replays the first matching interaction that was not replayed yet.
+    fn replay_request(&self, request: &reqwest::Request) -> crate::Result<reqwest::Response> {
+        let request = RecordedRequest::new(request);
+        let mut tape = self.tape.lock().unwrap();
+        let Tape {
+            interactions,
+            played,
+        } = &mut *tape;
+        let position = interactions
+            .iter()
+            .zip(played.iter())
+            .position(|(interaction, played)| !played && interaction.request.matches(&request));
+        let Some(position) = position else {
+            let query = request
+                .query
+                .map(|query| format!("?{query}"))
+                .unwrap_or_default();
+            return Err(ClientError::Replay(format!(
+                "{} {}{}",
+                request.method, request.path, query
+            )));
+        };
+        played[position] = true;
+        Ok(interactions[position].response.to_response())
+    }
+}
+
+impl Drop for Cassette {
+    fn drop(&mut self) {
+        if self.mode != CassetteMode::Replay || std::thread::panicking() {
+            return;
+        }
+        let tape = self.tape.get_mut().unwrap_or_else(|err| err.into_inner());
+        let unplayed: Vec<_> = tape
+            .interactions
+            .iter()
+            .zip(&tape.played)
+            .filter(|(_, played)| !**played)
+            .map(|(interaction, _)| {
+                let request = &interaction.request;
+                let query = request
+                    .query
+                    .as_ref()
+                    .map(|query| format!("?{query}"))
+                    .unwrap_or_default();
+                format!("{} {}{}", request.method, request.path, query)
+            })
+            .collect();
+        assert!(
+            unplayed.is_empty(),
+            "Interactions recorded in {} were not replayed: {}",
+            self.path.display(),
+            unplayed.join(", ")
+        );
+    }
+}
+
+/// Records the requests sent with the inner transport, or replays them, depending on the mode.
+#[derive(Debug)]
+pub(crate) struct CassetteTransport {
+    cassette: Cassette,
+    inner: Arc<dyn Transport>,
+}
+
+impl CassetteTransport {
+    pub(crate) fn new(cassette: Cassette, inner: Arc<dyn Transport>) -> Self {
+        Self { cassette, inner }
+    }
+}
+
+impl Transport for CassetteTransport {
+    fn execute(
+        &self,
+        request: reqwest::Request,
+    ) -> BoxFuture<'_, crate::Result<reqwest::Response>> {
+        Box::pin(async move {
+            match self.cassette.mode {
+                CassetteMode::Record => {
+                    self.cassette
+                        .record_request(self.inner.as_ref(), request)
+                        .await
+                }
+                CassetteMode::Replay => self.cassette.replay_request(&request),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::replay_client;
+
+    #[test]
+    fn api_key_is_redacted() {
+        let request = reqwest::Client::new()
+            .post("https://api.opsgenie.com/v2/alerts?limit=1")
+            .header("Authorization", "GenieKey secret")
+            .json(&serde_json::json!({ "message": "test" }))
+            .build()
+            .unwrap();
+        let recorded = RecordedRequest::new(&request);
+        assert_eq!(recorded.method, "POST");
+        assert_eq!(recorded.path, "/v2/alerts");
+        assert_eq!(recorded.query.as_deref(), Some("limit=1"));
+        assert_eq!(recorded.headers["authorization"], REDACTED);
+        assert_eq!(
+            recorded.body,
+            Some(serde_json::json!({ "message": "test" }))
+        );
+        let serialized = serde_json::to_string(&recorded).unwrap();
+        assert!(!serialized.contains("secret"));
+    }
+
+    #[test]
+    fn api_key_is_redacted_from_query_and_body() {
+        let url = "https://api.opsgenie.com/v1/json/webhooks?apiKey=secret&limit=1&geniekey=secret";
+        let request = reqwest::Client::new()
+            .post(url)
+            .json(&serde_json::json!({
+                "apiKey": "secret",
+                "integrations": [{ "name": "test", "ApiKey": "secret" }],
+            }))
+            .build()
+            .unwrap();
+        let recorded = RecordedRequest::new(&request);
+        assert_eq!(
+            recorded.query.as_deref(),
+            Some("apiKey=[REDACTED]&limit=1&geniekey=[REDACTED]")
+        );
+        assert_eq!(
+            recorded.body,
+            Some(serde_json::json!({
+                "apiKey": "[REDACTED]",
+                "integrations": [{ "name": "test", "ApiKey": "[REDACTED]" }],
+            }))
+        );
+        let serialized = serde_json::to_string(&recorded).unwrap();
+        assert!(!serialized.contains("secret"));
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "were not replayed: GET /v2/account")]
+    async fn unplayed_interactions_fail() {
+        let client = replay_client("account/get_account_response");
+        drop(client);
+    }
+
+    #[tokio::test]
+    async fn unmatched_requests_fail() {
+        let client = replay_client("account/get_account_response");
+        client.account().get().await.unwrap();
+        // Each interaction is replayed only once.
+        let err = client.account().get().await.unwrap_err();
+        assert!(
+            matches!(&err, ClientError::Replay(request) if request == "GET /v2/account"),
+            "{err:?}"
+        );
+        let err = client.team().list_teams().await.unwrap_err();
+        assert!(matches!(err, ClientError::Replay(_)), "{err:?}");
+    }
+}
@@ -1,17 +1,24 @@
-use crate::api::response::ApiResponse;
+use crate::api::{response::ApiResponse, ApiVersion};
 use api::response::ApiError;
+use cassette::{Cassette, CassetteTransport};
+use metrics::MetricsHook;
 use serde::{de::DeserializeOwned, Serialize};
+use std::sync::Arc;
+use transport::Transport;
 use url::Url;
 
+pub use builder::{OpsgenieClientBuilder, Region};
+
 pub mod api;
+mod builder;
+pub mod cassette;
 pub mod limits;
+pub mod metrics;
 pub mod pagination;
 pub mod query_builder;
 #[cfg(test)]
 pub(crate) mod test_utils;
-
-/// The Opsgenie API version to use.
-const API_VERSION: &str = "v2/";
+pub mod transport;
 
 #[derive(Debug, thiserror::Error)]
 pub enum ClientError {
@@ -19,6 +26,25 @@ pub enum ClientError {
     Client(#[from] reqwest::Error),
     #[error("Request failed: {0}")]
     Request(#[from] crate::api::response::ApiError),
+    #[error("Invalid URL received: {0}")]
+    Url(#[from] url::ParseError),
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("No recorded interaction for request: {0}")]
+    Replay(String),
+    #[error("Invalid client configuration: {0}")]
+    Config(String),
+}
+
+impl ClientError {
+    /// Status of the unsuccessful response, if one was received.
+    pub fn status(&self) -> Option<http::StatusCode> {
+        match self {
+            ClientError::Client(err) => err.status(),
+            ClientError::Request(err) => err.status,
+            _ => None,
+        }
+    }
 }
 
 pub type Result<T> = ::core::result::Result<T, ClientError>;
@@ -27,18 +53,97 @@ pub type Result<T> = ::core::result::Result<T, ClientError>;
 pub struct OpsgenieClient {
     base_url: Url,
     api_key: String,
+    /// Used to build requests. Requests are sent by `transport`.
     client: reqwest::Client,
+    transport: Arc<dyn Transport>,
+    metrics_hook: Option<Arc<dyn MetricsHook>>,
 }
 
 impl OpsgenieClient {
-    pub fn new(base_url: Url, api_key: String) -> Self {
-        let client = reqwest::Client::new();
-        let base_url = base_url.join(API_VERSION).unwrap();
-        Self {
-            base_url,
-            api_key,
-            client,
-        }
+    /// Creates a client with the default settings.
+    /// Fails with [`ClientError::Config`] if the base URL is invalid.
+    pub fn new(base_url: Url, api_key: String) -> Result<Self> {
+        Self::builder(api_key).base_url(base_url).build()
+    }
+
+    /// Creates a builder to configure the region and the connection settings.
+    pub fn builder(api_key: impl Into<String>) -> OpsgenieClientBuilder {
+        OpsgenieClientBuilder::new(api_key.into())
+    }
+
+    /// Creates a client sending requests with the pre-configured HTTP client,
+    /// e.g. with proxies, custom root certificates or timeouts.
+    /// Fails with [`ClientError::Config`] if the base URL is invalid.
+    pub fn with_http_client(
+        base_url: Url,
+        api_key: String,
+        client: reqwest::Client,
+    ) -> Result<Self> {
+        Self::builder(api_key)
+            .base_url(base_url)
+            .http_client(client)
+            .build()
+    }
+
+    /// Sends requests with the provided transport instead of the HTTP client.
+    /// See [`transport`] for details.
+    pub fn with_transport(mut self, transport: impl Transport) -> Self {
+        self.transport = Arc::new(transport);
+        self
+    }
+
+    /// Returns the base URL of the API, e.g. `https://api.opsgenie.com/`.
+    pub fn base_url(&self) -> &Url {
+        &self.base_url
+    }
+
+    /// Returns the transport used to send requests, e.g. to wrap it with middleware
+    /// and pass the result to [`Self::with_transport`].
+    pub fn transport(&self) -> Arc<dyn Transport> {
+        self.transport.clone()
+    }
+
+    /// Records the interactions with Opsgenie to the cassette, or replays them from it.
+    /// In recording mode, requests are sent with the current transport.
+    /// See [`cassette`] for details.
+    pub fn with_cassette(mut self, cassette: Cassette) -> Self {
+        self.transport = Arc::new(CassetteTransport::new(cassette, self.transport));
+        self
+    }
+
+    /// Reports every API request to the hook, e.g. to export latency metrics.
+    /// See [`metrics`] for details.
+    pub fn with_metrics_hook(mut self, hook: impl MetricsHook) -> Self {
+        self.metrics_hook = Some(Arc::new(hook));
+        self
+    }
+
+    pub fn account(&self) -> api::AccountApi<'_> {
+        api::AccountApi(self)
+    }
+
+    pub fn audit_log(&self) -> api::AuditLogApi<'_> {
+        api::AuditLogApi(self)
+    }
+
+    pub fn custom_user_role(&self) -> api::CustomUserRoleApi<'_> {
+        api::CustomUserRoleApi(self)
+    }
+
+    pub fn deployment(&self) -> api::DeploymentApi<'_> {
+        api::DeploymentApi(self)
+    }
+
+    pub fn forwarding_rule(&self) -> api::ForwardingRuleApi<'_> {
+        api::ForwardingRuleApi(self)
+    }
+
+    pub fn heartbeat(&self) -> api::HeartbeatApi<'_> {
+        api::HeartbeatApi(self)
+    }
+
+    pub fn incident(&self) -> api::IncidentApi<'_> {
+        api::IncidentApi(self)
     }
 
     pub fn on_call(&self) -> api::OnCallApi<'_> {
@@ -57,29 +162,88 @@ impl OpsgenieClient {
         api::TeamApi(self)
     }
 
-    pub(crate) async fn post<T: Serialize, R: DeserializeOwned>(
+    pub fn service(&self) -> api::ServiceApi<'_> {
+        api::ServiceApi(self)
+    }
+
+    pub(crate) async fn post<Q: Serialize, T: Serialize, R: DeserializeOwned>(
+        &self,
+        version: ApiVersion,
+        path: &str,
+        query: &Q,
+        body: &T,
+    ) -> Result<ApiResponse<R>> {
+        let url = self.url(version, path);
+        let request = self.client.post(url).query(query).json(body);
+        self.perform_request(request).await
+    }
+
+    pub(crate) async fn put<Q: Serialize, T: Serialize, R: DeserializeOwned>(
+        &self,
+        version: ApiVersion,
+        path: &str,
+        query: &Q,
+        body: &T,
+    ) -> Result<ApiResponse<R>> {
+        let url = self.url(version, path);
+        let request = self.client.put(url).query(query).json(body);
+        self.perform_request(request).await
+    }
+
+    pub(crate) async fn patch<T: Serialize, R: DeserializeOwned>(
         &self,
+        version: ApiVersion,
         path: &str,
         body: &T,
     ) -> Result<ApiResponse<R>> {
-        let url = self.url(path);
-        let request = self.client.post(url).json(body);
+        let url = self.url(version, path);
+        let request = self.client.patch(url).json(body);
+        self.perform_request(request).await
+    }
+
+    pub(crate) async fn delete<T: Serialize, R: DeserializeOwned>(
+        &self,
+        version: ApiVersion,
+        path: &str,
+        query: &T,
+    ) -> Result<ApiResponse<R>> {
+        let url = self.url(version, path);
+        let request = self.client.delete(url).query(query);
         self.perform_request(request).await
     }
 
     pub(crate) async fn get<T: Serialize, R: DeserializeOwned>(
         &self,
+        version: ApiVersion,
         path: &str,
         query: &T,
     ) -> Result<ApiResponse<R>> {
-        let url = self.url(path);
+        let url = self.url(version, path);
         let request = self.client.get(url).query(query);
         self.perform_request(request).await
     }
 
-    fn url(&self, path: &str) -> url::Url {
+    /// Performs a `GET` request, returning the response body as-is.
+    pub(crate) async fn get_text(&self, version: ApiVersion, path: &str) -> Result<String> {
+        let url = self.url(version, path);
+        let request = self.client.get(url);
+        let response = self.send(request).await?;
+        Ok(response.text().await?)
+    }
+
+    /// Performs a `GET` request to an external URL (e.g. a pre-signed download link).
+    /// The API key is not sent with the request.
+    pub(crate) async fn download(&self, url: Url) -> Result<reqwest::Response> {
+        let request = self.client.get(url).build()?;
+        let response = self.transport.execute(request).await?.error_for_status()?;
+        Ok(response)
+    }
+
+    /// Composes the URL of the endpoint, e.g. `https://api.opsgenie.com/v2/alerts`.
+    /// `path` is relative to the API version.
+    fn url(&self, version: ApiVersion, path: &str) -> url::Url {
         self.base_url
-            .join(path)
+            .join(&format!("{}/{path}", version.as_str()))
             .unwrap_or_else(|err| panic!("Invalid path provided: {path}: {err}"))
     }
 
@@ -87,11 +251,31 @@ impl OpsgenieClient {
         &self,
         request: reqwest::RequestBuilder,
     ) -> Result<ApiResponse<R>> {
-        let request = request.header("Authorization", format!("GenieKey {}", self.api_key));
-        let response = request.send().await?;
+        let response = self.send(request).await?;
+        let response: ApiResponse<R> = response.json().await?;
+        Ok(response)
+    }
+
+    /// Sends an authenticated request, converting unsuccessful responses into errors.
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<reqwest::Response> {
+        let request = request
+            .header("Authorization", format!("GenieKey {}", self.api_key))
+            .build()?;
+        let response = match &self.metrics_hook {
+            Some(hook) => {
+                metrics::execute_measured(
+                    self.transport.as_ref(),
+                    hook.as_ref(),
+                    self.base_url.path(),
+                    request,
+                )
+                .await?
+            }
+            None => self.transport.execute(request).await?,
+        };
+        let status = response.status();
         // TODO: If you get 503, you should retry the request, but if 429 you should wait a bit then retry the request *
-        if response.status().is_success() {
-            let response: ApiResponse<R> = response.json().await?;
+        if status.is_success() {
             Ok(response)
         } else {
             // TODO: Handle rate limiting.
@@ -99,7 +283,8 @@ impl OpsgenieClient {
             // Failed to deserialize response Object {"message": String("You are making too many requests! To avoid errors, we recommend you limit requests."),
             // "requestId": String("831e6aca-2dd3-475b-9acc-bd385b2f5e7a"), "took": Number(0.002)}: Error("missing field `data`", line: 0, column: 0)
 
-            let error: ApiError = response.json().await?;
+            let mut error: ApiError = response.json().await?;
+            error.status = Some(status);
             Err(ClientError::Request(error))
         }
     }
@@ -5,3 +5,6 @@
 
 /// Maximum value for the `limit` field in pagination queries.
 pub const PAGINATION_MAX_LIMIT: u32 = 100;
+
+/// Maximum value for the `limit` field when listing audit log files.
+pub const LOG_FILES_MAX_LIMIT: u32 = 1000;
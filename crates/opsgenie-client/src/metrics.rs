@@ -0,0 +1,253 @@
+//! Hook for collecting metrics about the requests sent to Opsgenie.
+//!
+//! The hook is called by [`OpsgenieClient`](crate::OpsgenieClient) after each API request,
+//! whether it succeeded or not:
+//!
+//! ```
+//! use opsgenie_client::{
+//!     metrics::{MetricsHook, RequestInfo},
+//!     OpsgenieClient,
+//! };
+//!
+//! #[derive(Debug)]
+//! struct Latency;
+//!
+//! impl MetricsHook for Latency {
+//!     fn on_request(&self, request: &RequestInfo) {
+//!         println!("{} {}: {:?}", request.method, request.endpoint, request.latency);
+//!     }
+//! }
+//!
+//! let url = "https://api.opsgenie.com".parse().unwrap();
+//! let client = OpsgenieClient::new(url, "api-key".into())
+//!     .unwrap()
+//!     .with_metrics_hook(Latency);
+//! ```
+//!
+//! The hook measures the whole transport of the client, including its middleware. To measure
+//! only the requests sent by an inner transport, e.g. without the time spent waiting in a
+//! client-side rate limiter, wrap that transport with [`MetricsTransport`] instead.
+
+use std::{
+    fmt,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use http::{Method, StatusCode};
+use url::Url;
+
+use crate::transport::{BoxFuture, Transport};
+
+/// Value of the `X-RateLimit-State` header for throttled requests.
+const THROTTLED: &str = "THROTTLED";
+
+/// Path segments that are not followed by identifiers, e.g. in `v2/alerts/count`.
+const ACTION_SEGMENTS: &[&str] = &["count", "list", "download", "requests"];
+
+/// Information about a request sent to Opsgenie.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct RequestInfo {
+    pub method: Method,
+    /// Path of the endpoint relative to the base URL, with the identifiers replaced
+    /// by `{id}`, e.g. `v2/schedules/{id}/on-calls`.
+    pub endpoint: String,
+    /// Status of the response, or `None` if no response was received.
+    pub status: Option<StatusCode>,
+    /// Time until the response headers were received.
+    pub latency: Duration,
+    /// Whether the request was rejected because of the rate limiting.
+    pub rate_limited: bool,
+}
+
+impl RequestInfo {
+    pub(crate) fn new(
+        method: Method,
+        path: &str,
+        response: Option<&reqwest::Response>,
+        latency: Duration,
+    ) -> Self {
+        let rate_limited = response.is_some_and(|response| {
+            response.status() == StatusCode::TOO_MANY_REQUESTS
+                || response
+                    .headers()
+                    .get("X-RateLimit-State")
+                    .is_some_and(|state| state == THROTTLED)
+        });
+        Self {
+            method,
+            endpoint: endpoint(path),
+            status: response.map(reqwest::Response::status),
+            latency,
+            rate_limited,
+        }
+    }
+}
+
+/// Receives information about the requests sent to Opsgenie.
+///
+/// The hook is called synchronously, so it should not block.
+pub trait MetricsHook: fmt::Debug + Send + Sync + 'static {
+    fn on_request(&self, request: &RequestInfo);
+}
+
+impl<T: MetricsHook + ?Sized> MetricsHook for Arc<T> {
+    fn on_request(&self, request: &RequestInfo) {
+        self.as_ref().on_request(request);
+    }
+}
+
+/// Transport reporting the requests sent with the inner transport to the hook.
+#[derive(Debug)]
+pub struct MetricsTransport {
+    inner: Arc<dyn Transport>,
+    hook: Arc<dyn MetricsHook>,
+    /// Path of the base URL, which is not part of the reported endpoints.
+    base_path: String,
+}
+
+impl MetricsTransport {
+    /// `base_url` is the one of the client, see [`crate::OpsgenieClient::base_url`].
+    pub fn new(inner: impl Transport, base_url: &Url, hook: impl MetricsHook) -> Self {
+        Self {
+            inner: Arc::new(inner),
+            hook: Arc::new(hook),
+            base_path: base_url.path().to_owned(),
+        }
+    }
+}
+
+impl Transport for MetricsTransport {
+    fn execute(
+        &self,
+        request: reqwest::Request,
+    ) -> BoxFuture<'_, crate::Result<reqwest::Response>> {
+        Box::pin(execute_measured(
+            self.inner.as_ref(),
+            self.hook.as_ref(),
+            &self.base_path,
+            request,
+        ))
+    }
+}
+
+/// Sends the request with the transport and reports it to the hook.
+pub(crate) async fn execute_measured(
+    transport: &dyn Transport,
+    hook: &dyn MetricsHook,
+    base_path: &str,
+    request: reqwest::Request,
+) -> crate::Result<reqwest::Response> {
+    let method = request.method().clone();
+    let path = request.url().path();
+    // Relative to the base URL, which may include a path prefix.
+    let path = path.strip_prefix(base_path).unwrap_or(path).to_owned();
+    let started = Instant::now();
+    let response = transport.execute(request).await;
+    let info = RequestInfo::new(method, &path, response.as_ref().ok(), started.elapsed());
+    hook.on_request(&info);
+    response
+}
+
+/// Replaces the identifiers in the path with `{id}`, so that the endpoints can be used as
+/// metric labels. Opsgenie paths alternate between resources and their identifiers,
+/// e.g. `v2/services/{id}/incident-rules/{id}`.
+fn endpoint(path: &str) -> String {
+    let mut segments = path.split('/').filter(|segment| !segment.is_empty());
+    let Some(version) = segments.next() else {
+        return String::new();
+    };
+    let mut endpoint = version.to_string();
+    let mut expect_identifier = false;
+    for segment in segments {
+        endpoint.push('/');
+        if expect_identifier && !ACTION_SEGMENTS.contains(&segment) {
+            endpoint.push_str("{id}");
+            expect_identifier = false;
+        } else {
+            endpoint.push_str(segment);
+            expect_identifier = true;
+        }
+    }
+    endpoint
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::test_utils::replay_client;
+
+    #[derive(Debug, Default)]
+    struct Recorder(Mutex<Vec<RequestInfo>>);
+
+    impl MetricsHook for Recorder {
+        fn on_request(&self, request: &RequestInfo) {
+            self.0.lock().unwrap().push(request.clone());
+        }
+    }
+
+    #[test]
+    fn endpoints() {
+        assert_eq!(endpoint("v2/alerts"), "v2/alerts");
+        assert_eq!(endpoint("v2/alerts/count"), "v2/alerts/count");
+        assert_eq!(endpoint("v2/teams/ops"), "v2/teams/{id}");
+        assert_eq!(
+            endpoint("v2/schedules/8418d193/on-calls"),
+            "v2/schedules/{id}/on-calls"
+        );
+        assert_eq!(
+            endpoint("v1/services/1/incident-rules/2"),
+            "v1/services/{id}/incident-rules/{id}"
+        );
+        assert_eq!(endpoint("v2/logs/list/"), "v2/logs/list");
+        assert_eq!(
+            endpoint("v2/logs/download/2024-07-19-17.json"),
+            "v2/logs/download/{id}"
+        );
+    }
+
+    #[tokio::test]
+    async fn requests_are_reported() {
+        let recorder = Arc::new(Recorder::default());
+        let client =
+            replay_client("on_call/whoisoncall_flat_response").with_metrics_hook(recorder.clone());
+        client
+            .on_call()
+            .whoisoncall("8418d193-2dab-4490-b331-8c02cdd196b7")
+            .await
+            .unwrap();
+        client.account().get().await.unwrap_err();
+
+        let requests = recorder.0.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].method, Method::GET);
+        assert_eq!(requests[0].endpoint, "v2/schedules/{id}/on-calls");
+        assert_eq!(requests[0].status, Some(StatusCode::OK));
+        assert!(!requests[0].rate_limited);
+        // The account request is not recorded in the cassette, so no response is received.
+        assert_eq!(requests[1].endpoint, "v2/account");
+        assert_eq!(requests[1].status, None);
+    }
+
+    #[tokio::test]
+    async fn inner_transport_is_reported() {
+        let recorder = Arc::new(Recorder::default());
+        let client = replay_client("on_call/whoisoncall_flat_response");
+        let transport =
+            MetricsTransport::new(client.transport(), client.base_url(), recorder.clone());
+        let client = client.with_transport(transport);
+        client
+            .on_call()
+            .whoisoncall("8418d193-2dab-4490-b331-8c02cdd196b7")
+            .await
+            .unwrap();
+
+        let requests = recorder.0.lock().unwrap();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].endpoint, "v2/schedules/{id}/on-calls");
+        assert_eq!(requests[0].status, Some(StatusCode::OK));
+    }
+}
@@ -0,0 +1,313 @@
+//! Local evaluation of search queries against alerts.
+//!
+//! Evaluation mimics the Opsgenie search semantics closely enough for tests and offline
+//! filtering, but is not guaranteed to be identical to the real API:
+//!
+//! - Text fields such as `message` match if the value occurs as a sequence of words.
+//! - Other fields are compared case-insensitively as a whole.
+//! - Numeric and date/time fields are compared numerically; date/time values are
+//!   milliseconds since the Unix epoch.
+//! - A query matches if any of the field values matches, e.g. `tag:critical` matches alerts
+//!   that have `critical` among their tags. Fields that are not present in the alert
+//!   don't match anything.
+
+use std::cmp::Ordering;
+
+use super::{
+    parser::{parse_expr, Expr},
+    Field, Operator, ParseError, ToFilter,
+};
+use crate::api::alert::response::Alert;
+
+/// Filter that can be matched against alerts without sending requests to Opsgenie.
+#[derive(Debug, Clone)]
+pub struct AlertMatcher {
+    expr: Expr,
+}
+
+impl AlertMatcher {
+    /// Creates a matcher for the filter.
+    ///
+    /// Returns an error if the rendered filter is not a valid search query.
+    pub fn new<F: ToFilter + ?Sized>(filter: &F) -> Result<Self, ParseError> {
+        Self::parse(&filter.to_filter())
+    }
+
+    /// Creates a matcher from a search query.
+    pub fn parse(query: &str) -> Result<Self, ParseError> {
+        Ok(Self {
+            expr: parse_expr(query)?,
+        })
+    }
+
+    /// Checks whether the alert matches the filter.
+    pub fn matches(&self, alert: &Alert) -> bool {
+        matches_alert(&self.expr, alert)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum FieldValue {
+    /// Free text, matched word by word.
+    Text(String),
+    /// Value matched as a whole.
+    Keyword(String),
+    Number(i64),
+}
+
+fn matches_alert(expr: &Expr, alert: &Alert) -> bool {
+    match expr {
+        Expr::Query {
+            field,
+            operator,
+            filter,
+        } => matches_values(filter, *operator, &field_values(field, alert)),
+        Expr::And(left, right) => matches_alert(left, alert) && matches_alert(right, alert),
+        Expr::Or(left, right) => matches_alert(left, alert) || matches_alert(right, alert),
+        Expr::Not(expr) => !matches_alert(expr, alert),
+        // Queries without a field are rejected by the parser.
+        Expr::Wildcard(_) | Expr::Value(_) => unreachable!("Value outside of a query"),
+    }
+}
+
+fn matches_values(filter: &Expr, operator: Operator, values: &[FieldValue]) -> bool {
+    match filter {
+        Expr::Value(expected) => values
+            .iter()
+            .any(|value| matches_value(value, operator, expected)),
+        Expr::Wildcard(prefix) => values.iter().any(|value| matches_prefix(value, prefix)),
+        Expr::And(left, right) => {
+            matches_values(left, operator, values) && matches_values(right, operator, values)
+        }
+        Expr::Or(left, right) => {
+            matches_values(left, operator, values) || matches_values(right, operator, values)
+        }
+        Expr::Not(filter) => !matches_values(filter, operator, values),
+        // Nested queries are rejected by the parser.
+        Expr::Query { .. } => unreachable!("Query inside of a filter"),
+    }
+}
+
+fn matches_value(value: &FieldValue, operator: Operator, expected: &str) -> bool {
+    let ordering = match value {
+        FieldValue::Text(text) if operator == Operator::ExactMatch => {
+            return contains_words(text, expected);
+        }
+        FieldValue::Text(text) | FieldValue::Keyword(text) => {
+            Some(text.to_lowercase().cmp(&expected.to_lowercase()))
+        }
+        FieldValue::Number(number) => expected
+            .parse::<i64>()
+            .ok()
+            .map(|expected| number.cmp(&expected)),
+    };
+    let Some(ordering) = ordering else {
+        return false;
+    };
+    match operator {
+        Operator::ExactMatch => ordering == Ordering::Equal,
+        Operator::Less => ordering == Ordering::Less,
+        Operator::LessOrEqual => ordering != Ordering::Greater,
+        Operator::Greater => ordering == Ordering::Greater,
+        Operator::GreaterOrEqual => ordering != Ordering::Less,
+    }
+}
+
+fn matches_prefix(value: &FieldValue, prefix: &str) -> bool {
+    let prefix = prefix.to_lowercase();
+    match value {
+        FieldValue::Text(text) => text
+            .split_whitespace()
+            .any(|word| word.to_lowercase().starts_with(&prefix)),
+        FieldValue::Keyword(keyword) => keyword.to_lowercase().starts_with(&prefix),
+        FieldValue::Number(number) => number.to_string().starts_with(&prefix),
+    }
+}
+
+/// Checks whether `phrase` occurs in `text` as a sequence of words.
+fn contains_words(text: &str, phrase: &str) -> bool {
+    let text: Vec<_> = text.split_whitespace().map(str::to_lowercase).collect();
+    let phrase: Vec<_> = phrase.split_whitespace().map(str::to_lowercase).collect();
+    if phrase.is_empty() {
+        return text.is_empty();
+    }
+    text.windows(phrase.len()).any(|window| window == phrase)
+}
+
+fn field_values(field: &Field, alert: &Alert) -> Vec<FieldValue> {
+    let keyword = |value: &str| vec![FieldValue::Keyword(value.to_string())];
+    let optional = |value: &Option<String>| {
+        value
+            .iter()
+            .map(|value| FieldValue::Keyword(value.clone()))
+            .collect()
+    };
+    let report = alert.report.as_ref();
+    match field {
+        Field::Message => vec![FieldValue::Text(alert.message.clone())],
+        Field::Alias => optional(&alert.alias),
+        Field::Status => keyword(&alert.status),
+        Field::Priority => keyword(&alert.priority),
+        Field::Tag => alert
+            .tags
+            .iter()
+            .flatten()
+            .map(|tag| FieldValue::Keyword(tag.clone()))
+            .collect(),
+        Field::Owner => optional(&alert.owner),
+        Field::Source => keyword(&alert.source),
+        Field::Acknowledged => keyword(&alert.acknowledged.to_string()),
+        Field::Snoozed => keyword(&alert.snoozed.to_string()),
+        Field::IsSeen => keyword(&alert.is_seen.to_string()),
+        Field::Count => vec![FieldValue::Number(alert.count as i64)],
+        Field::TinyId => keyword(&alert.tiny_id),
+        Field::CreatedAt => vec![FieldValue::Number(alert.created_at.timestamp_millis())],
+        Field::UpdatedAt => vec![FieldValue::Number(alert.updated_at.timestamp_millis())],
+        Field::LastOccurredAt => {
+            vec![FieldValue::Number(
+                alert.last_occurred_at.timestamp_millis(),
+            )]
+        }
+        Field::AcknowledgedBy => report.map_or_else(Vec::new, |r| optional(&r.acknowledged_by)),
+        Field::ClosedBy => report.map_or_else(Vec::new, |r| optional(&r.closed_by)),
+        Field::Teams => responder_values(alert, Some("team")),
+        // `team` is accepted by Opsgenie as well as `teams`.
+        Field::Custom(name) if name == "team" => responder_values(alert, Some("team")),
+        Field::Responders => responder_values(alert, None),
+        // Not available in the alert representation.
+        Field::Description
+        | Field::Entity
+        | Field::DetailsKey
+        | Field::DetailsValue
+        | Field::Custom(_) => Vec::new(),
+    }
+}
+
+/// Responders can be referred to by their ID, name or username.
+fn responder_values(alert: &Alert, responder_type: Option<&str>) -> Vec<FieldValue> {
+    alert
+        .responders
+        .iter()
+        .filter(|responder| responder_type.is_none_or(|ty| responder.r#type == ty))
+        .flat_map(|responder| {
+            [
+                Some(&responder.id),
+                responder.name.as_ref(),
+                responder.username.as_ref(),
+            ]
+        })
+        .flatten()
+        .map(|value| FieldValue::Keyword(value.clone()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query_builder::{AlertStatus, Priority, Query};
+
+    fn alert() -> Alert {
+        serde_json::from_str(
+            r#"{
+    "id": "70413a06-38d6-4c85-92b8-5ebc900d42e2",
+    "tinyId": "1791",
+    "alias": "event_573",
+    "message": "Our servers are in danger",
+    "status": "open",
+    "acknowledged": false,
+    "isSeen": true,
+    "tags": ["OverwriteQuietHours", "Critical"],
+    "snoozed": false,
+    "count": 79,
+    "lastOccurredAt": "2017-04-03T20:05:50.894Z",
+    "createdAt": "2017-03-21T20:32:52.353Z",
+    "updatedAt": "2017-04-03T20:32:57.301Z",
+    "source": "Isengard",
+    "owner": "morpheus@opsgenie.com",
+    "priority": "P4",
+    "responders": [
+        { "id": "4513b7ea-3b91-438f-b7e4-e3e54af9147c", "type": "team", "name": "ops_team" },
+        { "id": "bb4d9938-c3c2-455d-aaab-727aa701c0d8", "type": "user" }
+    ],
+    "report": {
+        "ackTime": 15702,
+        "acknowledgedBy": "agent_smith@opsgenie.com"
+    }
+}"#,
+        )
+        .unwrap()
+    }
+
+    fn matches(query: &str) -> bool {
+        AlertMatcher::parse(query).unwrap().matches(&alert())
+    }
+
+    #[test]
+    fn exact_match() {
+        assert!(matches("status:open"));
+        assert!(matches("status:OPEN"));
+        assert!(!matches("status:closed"));
+        assert!(matches("tag:critical"));
+        assert!(!matches("tag:minor"));
+        assert!(matches("alias:event_573"));
+        assert!(matches("acknowledgedBy:agent_smith@opsgenie.com"));
+        assert!(!matches("closedBy:neo@opsgenie.com"));
+        assert!(!matches("description:anything"));
+    }
+
+    #[test]
+    fn text_match() {
+        assert!(matches("message:danger"));
+        assert!(matches(r#"message:"in danger""#));
+        assert!(!matches(r#"message:"danger in""#));
+        assert!(!matches("message:dang"));
+        assert!(matches("message:dang*"));
+    }
+
+    #[test]
+    fn comparisons() {
+        assert!(matches("count>78"));
+        assert!(matches("count>=79"));
+        assert!(!matches("count>79"));
+        assert!(matches("count<=79"));
+        assert!(!matches("count<79"));
+        assert!(matches("priority<P5"));
+        assert!(!matches("priority<=P3"));
+        // 2017-03-21T20:32:52.353Z
+        assert!(matches("createdAt>=1490128372353"));
+        assert!(!matches("createdAt>1490128372353"));
+        assert!(!matches("count>many"));
+    }
+
+    #[test]
+    fn responders() {
+        assert!(matches("team:ops_team"));
+        assert!(matches("teams:4513b7ea-3b91-438f-b7e4-e3e54af9147c"));
+        assert!(!matches("teams:bb4d9938-c3c2-455d-aaab-727aa701c0d8"));
+        assert!(matches("responders:bb4d9938-c3c2-455d-aaab-727aa701c0d8"));
+    }
+
+    #[test]
+    fn combinations() {
+        assert!(matches("status:open AND tag:critical"));
+        assert!(!matches("status:open AND tag:minor"));
+        assert!(matches("status:closed OR tag:critical"));
+        assert!(matches("NOT status:closed"));
+        assert!(matches("tag:(minor OR critical)"));
+        assert!(!matches("tag:(minor AND critical)"));
+        assert!(matches("tag:(overwritequiethours AND critical)"));
+        assert!(matches("tag:(NOT minor)"));
+        assert!(matches("tag:crit*"));
+        assert!(!matches("owner:neo*"));
+    }
+
+    #[test]
+    fn builder_filters() {
+        let filter = Query::status(AlertStatus::Open)
+            .and(Query::priority(Priority::P4))
+            .and(Query::message("servers are"));
+        assert!(AlertMatcher::new(&filter).unwrap().matches(&alert()));
+        let filter = Query::status(AlertStatus::Open).and(Query::priority(Priority::P1));
+        assert!(!AlertMatcher::new(&filter).unwrap().matches(&alert()));
+    }
+}
@@ -0,0 +1,977 @@
+use std::{
+    fmt,
+    ops::{Bound, RangeBounds},
+};
+
+use chrono::{DateTime, TimeZone};
+
+pub use self::{
+    eval::AlertMatcher,
+    parser::{parse, ParseError, ParseErrorKind},
+};
+
+mod eval;
+mod parser;
+
+/// Alert fields known to the Opsgenie search syntax.
+///
+/// [Corresponding API page](https://support.atlassian.com/opsgenie/docs/search-queries-for-alerts/)
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Field {
+    Message,
+    Alias,
+    Description,
+    Status,
+    Priority,
+    Teams,
+    Tag,
+    Owner,
+    Responders,
+    Source,
+    Entity,
+    Acknowledged,
+    AcknowledgedBy,
+    ClosedBy,
+    Snoozed,
+    IsSeen,
+    Count,
+    TinyId,
+    CreatedAt,
+    UpdatedAt,
+    LastOccurredAt,
+    /// Key of an extra property of the alert, i.e. `details.key`.
+    DetailsKey,
+    /// Value of an extra property of the alert, i.e. `details.value`.
+    DetailsValue,
+    /// Any other field, rendered as-is.
+    Custom(String),
+}
+
+impl Field {
+    /// Creates a field from its name as used in search queries.
+    /// Unknown names are represented as [`Field::Custom`].
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "message" => Self::Message,
+            "alias" => Self::Alias,
+            "description" => Self::Description,
+            "status" => Self::Status,
+            "priority" => Self::Priority,
+            "teams" => Self::Teams,
+            "tag" => Self::Tag,
+            "owner" => Self::Owner,
+            "responders" => Self::Responders,
+            "source" => Self::Source,
+            "entity" => Self::Entity,
+            "acknowledged" => Self::Acknowledged,
+            "acknowledgedBy" => Self::AcknowledgedBy,
+            "closedBy" => Self::ClosedBy,
+            "snoozed" => Self::Snoozed,
+            "isSeen" => Self::IsSeen,
+            "count" => Self::Count,
+            "tinyId" => Self::TinyId,
+            "createdAt" => Self::CreatedAt,
+            "updatedAt" => Self::UpdatedAt,
+            "lastOccurredAt" => Self::LastOccurredAt,
+            "details.key" => Self::DetailsKey,
+            "details.value" => Self::DetailsValue,
+            _ => Self::Custom(name.to_string()),
+        }
+    }
+
+    /// Returns the name of the field as used in search queries.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Message => "message",
+            Self::Alias => "alias",
+            Self::Description => "description",
+            Self::Status => "status",
+            Self::Priority => "priority",
+            Self::Teams => "teams",
+            Self::Tag => "tag",
+            Self::Owner => "owner",
+            Self::Responders => "responders",
+            Self::Source => "source",
+            Self::Entity => "entity",
+            Self::Acknowledged => "acknowledged",
+            Self::AcknowledgedBy => "acknowledgedBy",
+            Self::ClosedBy => "closedBy",
+            Self::Snoozed => "snoozed",
+            Self::IsSeen => "isSeen",
+            Self::Count => "count",
+            Self::TinyId => "tinyId",
+            Self::CreatedAt => "createdAt",
+            Self::UpdatedAt => "updatedAt",
+            Self::LastOccurredAt => "lastOccurredAt",
+            Self::DetailsKey => "details.key",
+            Self::DetailsValue => "details.value",
+            Self::Custom(name) => name,
+        }
+    }
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Status of an alert.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AlertStatus {
+    Open,
+    Closed,
+}
+
+impl fmt::Display for AlertStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Open => "open",
+            Self::Closed => "closed",
+        })
+    }
+}
+
+/// Priority of an alert.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Priority {
+    P1,
+    P2,
+    P3,
+    P4,
+    P5,
+}
+
+impl Priority {
+    /// All the priorities, from the highest to the lowest.
+    pub const ALL: [Self; 5] = [Self::P1, Self::P2, Self::P3, Self::P4, Self::P5];
+}
+
+impl fmt::Display for Priority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+#[derive(Debug)]
+pub struct Query {
+    field: Field,
+    operator: Operator,
+    filter: Box<dyn ToFilter>,
+}
+
+/// Binding strength of the expression, from the weakest to the strongest.
+///
+/// Used to decide whether the expression has to be enclosed in parentheses
+/// when it is nested into another expression.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Precedence {
+    Or,
+    And,
+    Not,
+    /// Expressions that never need parentheses, e.g. queries and values.
+    Atom,
+}
+
+pub trait ToFilter: 'static + std::fmt::Debug + Send + Sync {
+    fn to_filter(&self) -> String;
+
+    fn precedence(&self) -> Precedence {
+        Precedence::Atom
+    }
+
+    fn and<R: ToFilter>(self, other: R) -> And
+    where
+        Self: Sized,
+    {
+        And::new(self, other)
+    }
+
+    fn or<R: ToFilter>(self, other: R) -> Or
+    where
+        Self: Sized,
+    {
+        Or::new(self, other)
+    }
+
+    fn not(self) -> Not
+    where
+        Self: Sized,
+    {
+        Not {
+            value: Box::new(self),
+        }
+    }
+
+    fn wildcard(self) -> Wildcard
+    where
+        Self: Sized,
+    {
+        Wildcard {
+            value: Box::new(self),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    ExactMatch,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Operator {
+    fn as_symbol(&self) -> &str {
+        match self {
+            Operator::ExactMatch => ":",
+            Operator::Less => "<",
+            Operator::LessOrEqual => "<=",
+            Operator::Greater => ">",
+            Operator::GreaterOrEqual => ">=",
+        }
+    }
+}
+
+impl Query {
+    fn with_operator<T: ToString>(field: T, filter: Box<dyn ToFilter>, operator: Operator) -> Self {
+        Self {
+            field: Field::from_name(&field.to_string()),
+            operator,
+            filter,
+        }
+    }
+
+    /// Creates an exact match query.
+    /// `field` can be either a [`Field`] or a field name.
+    pub fn new<T: ToString, F: ToFilter>(field: T, filter: F) -> Self {
+        Self::with_operator(field, Box::new(filter), Operator::ExactMatch)
+    }
+
+    pub fn less<T: ToString, F: ToFilter>(field: T, filter: F) -> Self {
+        Self::with_operator(field, Box::new(filter), Operator::Less)
+    }
+
+    pub fn less_or_equal<T: ToString, F: ToFilter>(field: T, filter: F) -> Self {
+        Self::with_operator(field, Box::new(filter), Operator::LessOrEqual)
+    }
+
+    pub fn greater<T: ToString, F: ToFilter>(field: T, filter: F) -> Self {
+        Self::with_operator(field, Box::new(filter), Operator::Greater)
+    }
+
+    pub fn greater_or_equal<T: ToString, F: ToFilter>(field: T, filter: F) -> Self {
+        Self::with_operator(field, Box::new(filter), Operator::GreaterOrEqual)
+    }
+
+    pub fn status(status: AlertStatus) -> Self {
+        Self::new(Field::Status, status)
+    }
+
+    pub fn priority(priority: Priority) -> Self {
+        Self::new(Field::Priority, priority)
+    }
+
+    pub fn teams<F: ToFilter>(team: F) -> Self {
+        Self::new(Field::Teams, team)
+    }
+
+    pub fn tag<F: ToFilter>(tag: F) -> Self {
+        Self::new(Field::Tag, tag)
+    }
+
+    pub fn owner<F: ToFilter>(owner: F) -> Self {
+        Self::new(Field::Owner, owner)
+    }
+
+    pub fn responders<F: ToFilter>(responder: F) -> Self {
+        Self::new(Field::Responders, responder)
+    }
+
+    pub fn source<F: ToFilter>(source: F) -> Self {
+        Self::new(Field::Source, source)
+    }
+
+    pub fn message<F: ToFilter>(message: F) -> Self {
+        Self::new(Field::Message, message)
+    }
+
+    pub fn alias<F: ToFilter>(alias: F) -> Self {
+        Self::new(Field::Alias, alias)
+    }
+
+    pub fn details_key<F: ToFilter>(key: F) -> Self {
+        Self::new(Field::DetailsKey, key)
+    }
+
+    pub fn details_value<F: ToFilter>(value: F) -> Self {
+        Self::new(Field::DetailsValue, value)
+    }
+
+    pub fn acknowledged(acknowledged: bool) -> Self {
+        Self::new(Field::Acknowledged, acknowledged)
+    }
+
+    pub fn snoozed(snoozed: bool) -> Self {
+        Self::new(Field::Snoozed, snoozed)
+    }
+
+    pub fn is_seen(is_seen: bool) -> Self {
+        Self::new(Field::IsSeen, is_seen)
+    }
+
+    /// Matches alerts created within `range`.
+    pub fn created_at<Tz: TimeZone>(range: impl RangeBounds<DateTime<Tz>>) -> TimeRange {
+        TimeRange::new(Field::CreatedAt, range)
+    }
+
+    /// Matches alerts updated within `range`.
+    pub fn updated_at<Tz: TimeZone>(range: impl RangeBounds<DateTime<Tz>>) -> TimeRange {
+        TimeRange::new(Field::UpdatedAt, range)
+    }
+}
+
+impl ToFilter for Query {
+    fn to_filter(&self) -> String {
+        format!(
+            "{}{}{}",
+            self.field,
+            self.operator.as_symbol(),
+            render_operand(self.filter.as_ref(), Precedence::Atom)
+        )
+    }
+}
+
+impl ToFilter for Box<dyn ToFilter> {
+    fn to_filter(&self) -> String {
+        self.as_ref().to_filter()
+    }
+
+    fn precedence(&self) -> Precedence {
+        self.as_ref().precedence()
+    }
+}
+
+/// Renders the operand of an expression, enclosing it in parentheses if it binds
+/// weaker than `min_precedence`.
+fn render_operand(operand: &dyn ToFilter, min_precedence: Precedence) -> String {
+    if operand.precedence() < min_precedence {
+        format!("({})", operand.to_filter())
+    } else {
+        operand.to_filter()
+    }
+}
+
+/// Renders the operand of `AND` or `OR`. Operands combined with the other operator are
+/// enclosed in parentheses, so that the filter doesn't depend on the precedence of `AND`
+/// over `OR` in the search syntax.
+fn render_boolean_operand(operand: &dyn ToFilter, operator: Precedence) -> String {
+    let precedence = operand.precedence();
+    if precedence == operator || precedence >= Precedence::Not {
+        operand.to_filter()
+    } else {
+        format!("({})", operand.to_filter())
+    }
+}
+
+impl<T: std::fmt::Debug + ToString + 'static + Send + Sync> ToFilter for T {
+    fn to_filter(&self) -> String {
+        escape(&self.to_string())
+    }
+}
+
+/// Characters that have a special meaning in the search syntax, including the Lucene
+/// metacharacters.
+const SPECIAL_CHARS: &[char] = &[
+    '"', '\\', ':', '(', ')', '<', '>', '=', '*', '+', '-', '!', '[', ']', '{', '}', '^', '~', '?',
+    '/', '&', '|',
+];
+
+/// Words that have a special meaning in the search syntax.
+const KEYWORDS: &[&str] = &["AND", "OR", "NOT"];
+
+/// Quotes the value if it can't be used in a query as-is.
+fn escape(value: &str) -> String {
+    let needs_quotes = value.is_empty()
+        || value
+            .chars()
+            .any(|c| c.is_whitespace() || SPECIAL_CHARS.contains(&c))
+        || KEYWORDS
+            .iter()
+            .any(|keyword| value.eq_ignore_ascii_case(keyword));
+    if !needs_quotes {
+        return value.to_string();
+    }
+
+    let mut escaped = String::with_capacity(value.len() + 2);
+    escaped.push('"');
+    for c in value.chars() {
+        if c == '"' || c == '\\' {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped.push('"');
+    escaped
+}
+
+/// Matches values of a date/time field within the range.
+/// Time is sent to Opsgenie as milliseconds since the Unix epoch.
+#[derive(Debug)]
+pub struct TimeRange {
+    field: Field,
+    start: Bound<i64>,
+    end: Bound<i64>,
+}
+
+impl TimeRange {
+    pub fn new<Tz: TimeZone>(field: Field, range: impl RangeBounds<DateTime<Tz>>) -> Self {
+        let to_millis = |bound: Bound<&DateTime<Tz>>| bound.map(DateTime::timestamp_millis);
+        Self {
+            field,
+            start: to_millis(range.start_bound()),
+            end: to_millis(range.end_bound()),
+        }
+    }
+}
+
+impl ToFilter for TimeRange {
+    fn to_filter(&self) -> String {
+        let field = self.field.as_str();
+        let start = match self.start {
+            Bound::Included(start) => Some(format!("{field}>={start}")),
+            Bound::Excluded(start) => Some(format!("{field}>{start}")),
+            Bound::Unbounded => None,
+        };
+        let end = match self.end {
+            Bound::Included(end) => Some(format!("{field}<={end}")),
+            Bound::Excluded(end) => Some(format!("{field}<{end}")),
+            Bound::Unbounded => None,
+        };
+        match (start, end) {
+            (Some(start), Some(end)) => format!("{start} AND {end}"),
+            (Some(bound), None) | (None, Some(bound)) => bound,
+            // Matches any alert, since every alert has the field set.
+            (None, None) => format!("{field}>=0"),
+        }
+    }
+
+    fn precedence(&self) -> Precedence {
+        match (self.start, self.end) {
+            (Bound::Unbounded, _) | (_, Bound::Unbounded) => Precedence::Atom,
+            _ => Precedence::And,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Wildcard {
+    value: Box<dyn ToFilter>,
+}
+
+impl ToFilter for Wildcard {
+    fn to_filter(&self) -> String {
+        format!("{}*", render_operand(self.value.as_ref(), Precedence::Atom))
+    }
+}
+
+#[derive(Debug)]
+pub struct Not {
+    value: Box<dyn ToFilter>,
+}
+
+impl ToFilter for Not {
+    fn to_filter(&self) -> String {
+        format!(
+            "NOT {}",
+            render_operand(self.value.as_ref(), Precedence::Not)
+        )
+    }
+
+    fn precedence(&self) -> Precedence {
+        Precedence::Not
+    }
+}
+
+#[derive(Debug)]
+pub struct And {
+    left: Box<dyn ToFilter>,
+    right: Box<dyn ToFilter>,
+}
+
+impl And {
+    fn new<L: ToFilter, R: ToFilter>(left: L, right: R) -> Self {
+        Self {
+            left: Box::new(left),
+            right: Box::new(right),
+        }
+    }
+}
+
+impl ToFilter for And {
+    fn to_filter(&self) -> String {
+        format!(
+            "{} AND {}",
+            render_boolean_operand(self.left.as_ref(), Precedence::And),
+            render_boolean_operand(self.right.as_ref(), Precedence::And)
+        )
+    }
+
+    fn precedence(&self) -> Precedence {
+        Precedence::And
+    }
+}
+
+#[derive(Debug)]
+pub struct Or {
+    left: Box<dyn ToFilter>,
+    right: Box<dyn ToFilter>,
+}
+
+impl Or {
+    fn new<L: ToFilter, R: ToFilter>(left: L, right: R) -> Self {
+        Self {
+            left: Box::new(left),
+            right: Box::new(right),
+        }
+    }
+}
+
+impl ToFilter for Or {
+    fn to_filter(&self) -> String {
+        format!(
+            "{} OR {}",
+            render_boolean_operand(self.left.as_ref(), Precedence::Or),
+            render_boolean_operand(self.right.as_ref(), Precedence::Or)
+        )
+    }
+
+    fn precedence(&self) -> Precedence {
+        Precedence::Or
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn basic_query() {
+        let query = Query::new("field", "value");
+        assert_eq!(query.to_filter(), "field:value");
+        let query = Query::less("field", 42);
+        assert_eq!(query.to_filter(), "field<42");
+        let query = Query::greater("field", 42);
+        assert_eq!(query.to_filter(), "field>42");
+        let query = Query::greater_or_equal("field", 42);
+        assert_eq!(query.to_filter(), "field>=42");
+        let query = Query::less_or_equal("field", 42);
+        assert_eq!(query.to_filter(), "field<=42");
+    }
+
+    #[test]
+    fn escaping() {
+        let query = Query::message("plain");
+        assert_eq!(query.to_filter(), "message:plain");
+        let query = Query::message("two words");
+        assert_eq!(query.to_filter(), "message:\"two words\"");
+        let query = Query::message(r#"say "hi""#);
+        assert_eq!(query.to_filter(), r#"message:"say \"hi\"""#);
+        let query = Query::message(r"C:\path");
+        assert_eq!(query.to_filter(), r#"message:"C:\\path""#);
+        let query = Query::message("f(x)");
+        assert_eq!(query.to_filter(), "message:\"f(x)\"");
+        let query = Query::tag("a&&b");
+        assert_eq!(query.to_filter(), "tag:\"a&&b\"");
+        let query = Query::message("foo~");
+        assert_eq!(query.to_filter(), "message:\"foo~\"");
+        let query = Query::message("user@example.com_1.2");
+        assert_eq!(query.to_filter(), "message:user@example.com_1.2");
+        let query = Query::tag("or");
+        assert_eq!(query.to_filter(), "tag:\"or\"");
+        let query = Query::tag("");
+        assert_eq!(query.to_filter(), "tag:\"\"");
+    }
+
+    #[test]
+    fn typed_fields() {
+        assert_eq!(Query::status(AlertStatus::Open).to_filter(), "status:open");
+        assert_eq!(Query::priority(Priority::P2).to_filter(), "priority:P2");
+        assert_eq!(Query::acknowledged(true).to_filter(), "acknowledged:true");
+        assert_eq!(
+            Query::details_key("region").to_filter(),
+            "details.key:region"
+        );
+        assert_eq!(
+            Query::new("lastOccurredAt", 0).to_filter(),
+            Query::new(Field::LastOccurredAt, 0).to_filter()
+        );
+        assert_eq!(Field::from_name("custom"), Field::Custom("custom".into()));
+    }
+
+    #[test]
+    fn time_ranges() {
+        let start = DateTime::from_timestamp_millis(1_000).unwrap();
+        let end = DateTime::from_timestamp_millis(2_000).unwrap();
+        assert_eq!(
+            Query::created_at(start..end).to_filter(),
+            "createdAt>=1000 AND createdAt<2000"
+        );
+        assert_eq!(
+            Query::updated_at(start..=end).to_filter(),
+            "updatedAt>=1000 AND updatedAt<=2000"
+        );
+        assert_eq!(Query::created_at(start..).to_filter(), "createdAt>=1000");
+        assert_eq!(Query::created_at(..end).to_filter(), "createdAt<2000");
+    }
+
+    #[test]
+    fn combinations() {
+        let query = Query::new("field", "value")
+            .and(Query::less("field2", 42).not())
+            .or(Query::new("field3", "lorem".or("ipsum".wildcard())));
+        assert_eq!(
+            query.to_filter(),
+            "(field:value AND NOT field2<42) OR field3:(lorem OR ipsum*)"
+        );
+    }
+
+    #[test]
+    fn precedence() {
+        let a = || Query::tag("a");
+        let b = || Query::tag("b");
+        let c = || Query::tag("c");
+        assert_eq!(a().and(b()).not().to_filter(), "NOT (tag:a AND tag:b)");
+        assert_eq!(a().not().and(b()).to_filter(), "NOT tag:a AND tag:b");
+        assert_eq!(a().not().not().to_filter(), "NOT NOT tag:a");
+        assert_eq!(
+            a().or(b()).and(c()).to_filter(),
+            "(tag:a OR tag:b) AND tag:c"
+        );
+        assert_eq!(
+            a().and(b().or(c())).to_filter(),
+            "tag:a AND (tag:b OR tag:c)"
+        );
+        // Operators are never mixed without parentheses.
+        assert_eq!(
+            a().and(b()).or(c()).to_filter(),
+            "(tag:a AND tag:b) OR tag:c"
+        );
+        assert_eq!(
+            a().or(b().and(c())).to_filter(),
+            "tag:a OR (tag:b AND tag:c)"
+        );
+        assert_eq!(
+            a().and(b()).and(c()).to_filter(),
+            "tag:a AND tag:b AND tag:c"
+        );
+        assert_eq!(a().or(b().or(c())).to_filter(), "tag:a OR tag:b OR tag:c");
+        assert_eq!(Query::tag("a".not()).to_filter(), "tag:(NOT a)");
+        let start = DateTime::from_timestamp_millis(1_000).unwrap();
+        let end = DateTime::from_timestamp_millis(2_000).unwrap();
+        assert_eq!(
+            Query::created_at(start..end).not().to_filter(),
+            "NOT (createdAt>=1000 AND createdAt<2000)"
+        );
+        assert_eq!(
+            Query::created_at(start..end).or(a()).to_filter(),
+            "(createdAt>=1000 AND createdAt<2000) OR tag:a"
+        );
+    }
+
+    mod properties {
+        use super::super::*;
+        use crate::api::alert::response::Alert;
+        use proptest::prelude::*;
+
+        const TAGS: usize = 4;
+
+        /// Boolean expression over the presence of tags.
+        #[derive(Debug, Clone)]
+        enum Expr {
+            Tag(usize),
+            /// Combination of values inside a single `tag` query.
+            Values(ValueExpr),
+            And(Box<Expr>, Box<Expr>),
+            Or(Box<Expr>, Box<Expr>),
+            Not(Box<Expr>),
+        }
+
+        #[derive(Debug, Clone)]
+        enum ValueExpr {
+            Tag(usize),
+            And(Box<ValueExpr>, Box<ValueExpr>),
+            Or(Box<ValueExpr>, Box<ValueExpr>),
+            Not(Box<ValueExpr>),
+        }
+
+        /// Distinct tag names, including the characters that have to be escaped.
+        fn tag_names() -> impl Strategy<Value = Vec<String>> {
+            proptest::collection::hash_set(
+                r#"[a-zA-Z0-9 _.@"\\:()<>=*+\-!\[\]{}^~?/&|]{1,5}"#,
+                TAGS,
+            )
+            .prop_map(|names| names.into_iter().collect())
+        }
+
+        impl ValueExpr {
+            fn eval(&self, tags: &[bool]) -> bool {
+                match self {
+                    ValueExpr::Tag(index) => tags[*index],
+                    ValueExpr::And(left, right) => left.eval(tags) && right.eval(tags),
+                    ValueExpr::Or(left, right) => left.eval(tags) || right.eval(tags),
+                    ValueExpr::Not(value) => !value.eval(tags),
+                }
+            }
+
+            fn build(&self, names: &[String]) -> Box<dyn ToFilter> {
+                match self {
+                    ValueExpr::Tag(index) => Box::new(names[*index].clone()),
+                    ValueExpr::And(left, right) => {
+                        Box::new(left.build(names).and(right.build(names)))
+                    }
+                    ValueExpr::Or(left, right) => {
+                        Box::new(left.build(names).or(right.build(names)))
+                    }
+                    ValueExpr::Not(value) => Box::new(value.build(names).not()),
+                }
+            }
+        }
+
+        impl Expr {
+            fn eval(&self, tags: &[bool]) -> bool {
+                match self {
+                    Expr::Tag(index) => tags[*index],
+                    Expr::Values(values) => values.eval(tags),
+                    Expr::And(left, right) => left.eval(tags) && right.eval(tags),
+                    Expr::Or(left, right) => left.eval(tags) || right.eval(tags),
+                    Expr::Not(expr) => !expr.eval(tags),
+                }
+            }
+
+            fn build(&self, names: &[String]) -> Box<dyn ToFilter> {
+                match self {
+                    Expr::Tag(index) => Box::new(Query::tag(names[*index].clone())),
+                    Expr::Values(values) => Box::new(Query::tag(values.build(names))),
+                    Expr::And(left, right) => Box::new(left.build(names).and(right.build(names))),
+                    Expr::Or(left, right) => Box::new(left.build(names).or(right.build(names))),
+                    Expr::Not(expr) => Box::new(expr.build(names).not()),
+                }
+            }
+        }
+
+        fn value_expr() -> impl Strategy<Value = ValueExpr> {
+            (0..TAGS)
+                .prop_map(ValueExpr::Tag)
+                .prop_recursive(3, 8, 2, |inner| {
+                    prop_oneof![
+                        (inner.clone(), inner.clone())
+                            .prop_map(|(l, r)| ValueExpr::And(Box::new(l), Box::new(r))),
+                        (inner.clone(), inner.clone())
+                            .prop_map(|(l, r)| ValueExpr::Or(Box::new(l), Box::new(r))),
+                        inner.prop_map(|value| ValueExpr::Not(Box::new(value))),
+                    ]
+                })
+        }
+
+        fn expr() -> impl Strategy<Value = Expr> {
+            prop_oneof![
+                3 => (0..TAGS).prop_map(Expr::Tag),
+                1 => value_expr().prop_map(Expr::Values),
+            ]
+            .prop_recursive(4, 16, 2, |inner| {
+                prop_oneof![
+                    (inner.clone(), inner.clone())
+                        .prop_map(|(l, r)| Expr::And(Box::new(l), Box::new(r))),
+                    (inner.clone(), inner.clone())
+                        .prop_map(|(l, r)| Expr::Or(Box::new(l), Box::new(r))),
+                    inner.prop_map(|expr| Expr::Not(Box::new(expr))),
+                ]
+            })
+        }
+
+        /// Evaluates a rendered filter over the presence of tags, independently of the parser
+        /// of the crate. Operators can't be mixed without parentheses, so the result doesn't
+        /// depend on their precedence.
+        struct RenderedFilter<'a> {
+            tokens: Vec<&'a str>,
+            position: usize,
+            names: &'a [String],
+        }
+
+        impl<'a> RenderedFilter<'a> {
+            fn eval(filter: &'a str, tags: &[bool], names: &'a [String]) -> bool {
+                let mut tokens = Vec::new();
+                let mut word_start = None;
+                let mut in_quotes = false;
+                let mut escaped = false;
+                for (i, c) in filter.char_indices() {
+                    if in_quotes {
+                        match c {
+                            _ if escaped => escaped = false,
+                            '\\' => escaped = true,
+                            '"' => in_quotes = false,
+                            _ => {}
+                        }
+                    } else if c == '(' || c == ')' || c == ' ' {
+                        if let Some(start) = word_start.take() {
+                            tokens.push(&filter[start..i]);
+                        }
+                        if c != ' ' {
+                            tokens.push(&filter[i..=i]);
+                        }
+                    } else {
+                        in_quotes = c == '"';
+                        word_start.get_or_insert(i);
+                    }
+                }
+                tokens.extend(word_start.map(|start| &filter[start..]));
+
+                let mut filter = Self {
+                    tokens,
+                    position: 0,
+                    names,
+                };
+                let value = filter.expr(tags, false);
+                assert_eq!(filter.next(), None, "Unexpected trailing tokens");
+                value
+            }
+
+            fn next(&mut self) -> Option<&'a str> {
+                let token = self.tokens.get(self.position).copied();
+                self.position += 1;
+                token
+            }
+
+            fn peek(&self) -> Option<&'a str> {
+                self.tokens.get(self.position).copied()
+            }
+
+            /// Operands joined by the same operator. `in_tag` is set within `tag:(...)`,
+            /// where the operands are tag names.
+            fn expr(&mut self, tags: &[bool], in_tag: bool) -> bool {
+                let mut value = self.unary(tags, in_tag);
+                let mut operator = None;
+                while let Some(next @ ("AND" | "OR")) = self.peek() {
+                    assert!(
+                        operator.is_none_or(|operator| operator == next),
+                        "AND and OR are mixed without parentheses"
+                    );
+                    operator = Some(next);
+                    self.next();
+                    let operand = self.unary(tags, in_tag);
+                    value = if next == "AND" {
+                        value && operand
+                    } else {
+                        value || operand
+                    };
+                }
+                value
+            }
+
+            fn unary(&mut self, tags: &[bool], in_tag: bool) -> bool {
+                match self.next().expect("Unexpected end of the filter") {
+                    "NOT" => !self.unary(tags, in_tag),
+                    "(" => {
+                        let value = self.expr(tags, in_tag);
+                        assert_eq!(self.next(), Some(")"));
+                        value
+                    }
+                    "tag:" if !in_tag => {
+                        assert_eq!(self.next(), Some("("));
+                        let value = self.expr(tags, true);
+                        assert_eq!(self.next(), Some(")"));
+                        value
+                    }
+                    word => {
+                        let name = if in_tag {
+                            word
+                        } else {
+                            word.strip_prefix("tag:").expect("Unexpected field")
+                        };
+                        let name = match name.strip_prefix('"') {
+                            Some(quoted) => {
+                                let quoted = quoted.strip_suffix('"').expect("Unclosed quotes");
+                                let mut name = String::new();
+                                let mut chars = quoted.chars();
+                                while let Some(c) = chars.next() {
+                                    name.push(if c == '\\' { chars.next().unwrap() } else { c });
+                                }
+                                name
+                            }
+                            None => {
+                                // Lucene metacharacters are only allowed in quoted values.
+                                assert!(
+                                    name.chars().all(
+                                        |c| c.is_alphanumeric() || matches!(c, '_' | '.' | '@')
+                                    ),
+                                    "Unquoted value: {name}"
+                                );
+                                name.to_owned()
+                            }
+                        };
+                        (0..TAGS).any(|index| tags[index] && self.names[index] == name)
+                    }
+                }
+            }
+        }
+
+        fn alert(tags: &[bool], names: &[String]) -> Alert {
+            let tags: Vec<_> = (0..TAGS)
+                .filter(|index| tags[*index])
+                .map(|index| names[index].clone())
+                .collect();
+            serde_json::from_value(serde_json::json!({
+                "id": "70413a06-38d6-4c85-92b8-5ebc900d42e2",
+                "tinyId": "1791",
+                "message": "message",
+                "status": "open",
+                "acknowledged": false,
+                "isSeen": false,
+                "tags": tags,
+                "snoozed": false,
+                "count": 1,
+                "lastOccurredAt": "2017-04-03T20:05:50.894Z",
+                "createdAt": "2017-03-21T20:32:52.353Z",
+                "updatedAt": "2017-04-03T20:32:57.301Z",
+                "source": "source",
+                "priority": "P3",
+                "responders": []
+            }))
+            .unwrap()
+        }
+
+        proptest! {
+            #[test]
+            fn rendered_filter_is_equivalent(
+                expr in expr(),
+                tags in proptest::collection::vec(any::<bool>(), TAGS),
+                names in tag_names(),
+            ) {
+                let filter = expr.build(&names);
+                let rendered = filter.to_filter();
+                prop_assert_eq!(
+                    RenderedFilter::eval(&rendered, &tags, &names),
+                    expr.eval(&tags),
+                    "filter: {}",
+                    rendered
+                );
+                let matcher = AlertMatcher::new(&filter).unwrap();
+                prop_assert_eq!(
+                    matcher.matches(&alert(&tags, &names)),
+                    expr.eval(&tags),
+                    "filter: {}",
+                    rendered
+                );
+            }
+
+            #[test]
+            fn rendering_is_stable(expr in expr(), names in tag_names()) {
+                let rendered = expr.build(&names).to_filter();
+                let reparsed = parse(&rendered).unwrap();
+                prop_assert_eq!(reparsed.to_filter(), rendered);
+            }
+        }
+    }
+}
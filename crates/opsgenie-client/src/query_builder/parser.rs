@@ -0,0 +1,569 @@
+//! Parser for the Opsgenie search syntax.
+//!
+//! Supported grammar:
+//!
+//! ```text
+//! expr        := and ("OR" and)*
+//! and         := unary ("AND" unary)*
+//! unary       := "NOT" unary | "(" expr ")" | field operator filter_unary
+//! operator    := ":" | "=" | "<" | "<=" | ">" | ">="
+//! filter      := filter_and ("OR" filter_and)*
+//! filter_and  := filter_unary ("AND" filter_unary)*
+//! filter_unary := "NOT" filter_unary | "(" filter ")" | value ["*"]
+//! ```
+//!
+//! Filters combining several values must be enclosed in parentheses, e.g. `tag:(a OR b)`.
+//! Keywords are case-insensitive. Values may be quoted, in which case `\` escapes
+//! the next character.
+//!
+//! Untrusted queries are accepted: the nesting and the number of operators are limited,
+//! so that deeply nested queries are rejected rather than overflowing the stack.
+
+use std::fmt;
+
+use super::{Field, Operator, Query, ToFilter};
+
+/// Maximum number of nested parentheses and negations.
+/// Queries are parsed and rendered recursively, so the depth is limited to keep the stack bounded.
+const MAX_NESTING: usize = 32;
+/// Maximum number of `AND` and `OR` operators, each of which nests the expression further.
+const MAX_OPERATORS: usize = 1_000;
+
+/// Error returned when the query can't be parsed.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{kind} at position {position}")]
+pub struct ParseError {
+    /// Byte offset in the input where the error was detected.
+    pub position: usize,
+    pub kind: ParseErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    UnexpectedEnd,
+    UnexpectedToken(String),
+    UnterminatedQuote,
+    ExpectedField,
+    ExpectedOperator,
+    ExpectedValue,
+    UnclosedParenthesis,
+    TooDeeplyNested,
+    TooManyOperators,
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnexpectedEnd => f.write_str("unexpected end of query"),
+            Self::UnexpectedToken(token) => write!(f, "unexpected `{token}`"),
+            Self::UnterminatedQuote => f.write_str("unterminated quoted value"),
+            Self::ExpectedField => f.write_str("expected a field name"),
+            Self::ExpectedOperator => f.write_str("expected an operator"),
+            Self::ExpectedValue => f.write_str("expected a value"),
+            Self::UnclosedParenthesis => f.write_str("unclosed parenthesis"),
+            Self::TooDeeplyNested => write!(f, "more than {MAX_NESTING} nested expressions"),
+            Self::TooManyOperators => write!(f, "more than {MAX_OPERATORS} operators"),
+        }
+    }
+}
+
+/// Parses an Opsgenie search query.
+pub fn parse(input: &str) -> Result<Box<dyn ToFilter>, ParseError> {
+    parse_expr(input).map(Expr::into_filter)
+}
+
+/// Parses an Opsgenie search query into the intermediate representation.
+pub(super) fn parse_expr(input: &str) -> Result<Expr, ParseError> {
+    let tokens = tokenize(input)?;
+    let mut parser = Parser {
+        tokens,
+        position: 0,
+        input_len: input.len(),
+        depth: 0,
+        operators: 0,
+    };
+    let expr = parser.expr()?;
+    match parser.peek() {
+        None => Ok(expr),
+        Some(token) => Err(token.unexpected()),
+    }
+}
+
+/// Intermediate representation of a parsed query.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum Expr {
+    Query {
+        field: Field,
+        operator: Operator,
+        filter: Box<Expr>,
+    },
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    /// Prefix of the value matched by the wildcard.
+    Wildcard(String),
+    Value(String),
+}
+
+impl Expr {
+    fn into_filter(self) -> Box<dyn ToFilter> {
+        match self {
+            Self::Query {
+                field,
+                operator,
+                filter,
+            } => Box::new(Query::with_operator(field, filter.into_filter(), operator)),
+            Self::And(left, right) => Box::new(left.into_filter().and(right.into_filter())),
+            Self::Or(left, right) => Box::new(left.into_filter().or(right.into_filter())),
+            Self::Not(value) => Box::new(value.into_filter().not()),
+            Self::Wildcard(value) => Box::new(value.wildcard()),
+            Self::Value(value) => Box::new(value),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    OpenParen,
+    CloseParen,
+    Operator(Operator),
+    Star,
+    And,
+    Or,
+    Not,
+    Word(String),
+    Quoted(String),
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    position: usize,
+    /// Whether the token directly follows the previous one, without whitespace.
+    adjacent: bool,
+    text: String,
+}
+
+impl Token {
+    fn unexpected(&self) -> ParseError {
+        ParseError {
+            position: self.position,
+            kind: ParseErrorKind::UnexpectedToken(self.text.clone()),
+        }
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    !c.is_whitespace() && !matches!(c, '(' | ')' | ':' | '=' | '<' | '>' | '*' | '"')
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.char_indices().peekable();
+    let mut adjacent = false;
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            adjacent = false;
+            continue;
+        }
+
+        let kind = match c {
+            '(' | ')' | '*' | ':' | '=' => {
+                chars.next();
+                match c {
+                    '(' => TokenKind::OpenParen,
+                    ')' => TokenKind::CloseParen,
+                    '*' => TokenKind::Star,
+                    _ => TokenKind::Operator(Operator::ExactMatch),
+                }
+            }
+            '<' | '>' => {
+                chars.next();
+                let or_equal = chars.next_if(|&(_, c)| c == '=').is_some();
+                TokenKind::Operator(match (c, or_equal) {
+                    ('<', false) => Operator::Less,
+                    ('<', true) => Operator::LessOrEqual,
+                    ('>', false) => Operator::Greater,
+                    _ => Operator::GreaterOrEqual,
+                })
+            }
+            '"' => {
+                chars.next();
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        None => {
+                            return Err(ParseError {
+                                position: start,
+                                kind: ParseErrorKind::UnterminatedQuote,
+                            })
+                        }
+                        Some((_, '"')) => break,
+                        Some((_, '\\')) => match chars.next() {
+                            Some((_, escaped)) => value.push(escaped),
+                            None => {
+                                return Err(ParseError {
+                                    position: start,
+                                    kind: ParseErrorKind::UnterminatedQuote,
+                                })
+                            }
+                        },
+                        Some((_, c)) => value.push(c),
+                    }
+                }
+                TokenKind::Quoted(value)
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some((_, c)) = chars.next_if(|&(_, c)| is_word_char(c)) {
+                    word.push(c);
+                }
+                match word.to_ascii_uppercase().as_str() {
+                    "AND" => TokenKind::And,
+                    "OR" => TokenKind::Or,
+                    "NOT" => TokenKind::Not,
+                    _ => TokenKind::Word(word),
+                }
+            }
+        };
+        let end = chars.peek().map_or(input.len(), |&(position, _)| position);
+        tokens.push(Token {
+            kind,
+            position: start,
+            adjacent,
+            text: input[start..end].to_string(),
+        });
+        adjacent = true;
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    input_len: usize,
+    /// Number of parentheses and negations enclosing the current token.
+    depth: usize,
+    /// Number of `AND` and `OR` operators parsed so far.
+    operators: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Result<Token, ParseError> {
+        let token = self.tokens.get(self.position).cloned().ok_or(ParseError {
+            position: self.input_len,
+            kind: ParseErrorKind::UnexpectedEnd,
+        })?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    /// Consumes the next token if it's the binary operator.
+    fn next_operator(&mut self, kind: &TokenKind) -> Result<bool, ParseError> {
+        let position = match self.peek() {
+            Some(token) if token.kind == *kind => token.position,
+            _ => return Ok(false),
+        };
+        self.position += 1;
+        self.operators += 1;
+        if self.operators > MAX_OPERATORS {
+            return Err(ParseError {
+                position,
+                kind: ParseErrorKind::TooManyOperators,
+            });
+        }
+        Ok(true)
+    }
+
+    /// Enters the parentheses or negation opened by the token.
+    /// [`Self::leave`] must be called once the enclosed expression is parsed.
+    fn enter(&mut self, token: &Token) -> Result<(), ParseError> {
+        self.depth += 1;
+        if self.depth > MAX_NESTING {
+            return Err(ParseError {
+                position: token.position,
+                kind: ParseErrorKind::TooDeeplyNested,
+            });
+        }
+        Ok(())
+    }
+
+    fn leave(&mut self) {
+        self.depth -= 1;
+    }
+
+    fn expect_close_paren(&mut self, open: &Token) -> Result<(), ParseError> {
+        match self.peek() {
+            Some(token) if token.kind == TokenKind::CloseParen => {
+                self.position += 1;
+                Ok(())
+            }
+            Some(token) => Err(token.unexpected()),
+            None => Err(ParseError {
+                position: open.position,
+                kind: ParseErrorKind::UnclosedParenthesis,
+            }),
+        }
+    }
+
+    fn expr(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.and()?;
+        while self.next_operator(&TokenKind::Or)? {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.unary()?;
+        while self.next_operator(&TokenKind::And)? {
+            expr = Expr::And(Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Expr, ParseError> {
+        let token = self.next()?;
+        match token.kind {
+            TokenKind::Not => {
+                self.enter(&token)?;
+                let expr = self.unary()?;
+                self.leave();
+                Ok(Expr::Not(Box::new(expr)))
+            }
+            TokenKind::OpenParen => {
+                self.enter(&token)?;
+                let expr = self.expr()?;
+                self.expect_close_paren(&token)?;
+                self.leave();
+                Ok(expr)
+            }
+            TokenKind::Word(field) => {
+                let operator = match self.next() {
+                    Ok(Token {
+                        kind: TokenKind::Operator(operator),
+                        ..
+                    }) => operator,
+                    Ok(token) => {
+                        return Err(ParseError {
+                            position: token.position,
+                            kind: ParseErrorKind::ExpectedOperator,
+                        })
+                    }
+                    Err(err) => {
+                        return Err(ParseError {
+                            kind: ParseErrorKind::ExpectedOperator,
+                            ..err
+                        })
+                    }
+                };
+                let filter = if operator == Operator::ExactMatch {
+                    self.filter_unary()?
+                } else {
+                    // Comparisons only accept a single value.
+                    self.value()?
+                };
+                Ok(Expr::Query {
+                    field: Field::from_name(&field),
+                    operator,
+                    filter: Box::new(filter),
+                })
+            }
+            _ => Err(ParseError {
+                position: token.position,
+                kind: ParseErrorKind::ExpectedField,
+            }),
+        }
+    }
+
+    fn filter(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.filter_and()?;
+        while self.next_operator(&TokenKind::Or)? {
+            expr = Expr::Or(Box::new(expr), Box::new(self.filter_and()?));
+        }
+        Ok(expr)
+    }
+
+    fn filter_and(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.filter_unary()?;
+        while self.next_operator(&TokenKind::And)? {
+            expr = Expr::And(Box::new(expr), Box::new(self.filter_unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn filter_unary(&mut self) -> Result<Expr, ParseError> {
+        match self.peek().map(|token| &token.kind) {
+            Some(TokenKind::Not) => {
+                let not = self.next()?;
+                self.enter(&not)?;
+                let expr = self.filter_unary()?;
+                self.leave();
+                Ok(Expr::Not(Box::new(expr)))
+            }
+            Some(TokenKind::OpenParen) => {
+                let open = self.next()?;
+                self.enter(&open)?;
+                let expr = self.filter()?;
+                self.expect_close_paren(&open)?;
+                self.leave();
+                Ok(expr)
+            }
+            _ => self.value(),
+        }
+    }
+
+    fn value(&mut self) -> Result<Expr, ParseError> {
+        let token = self.next().map_err(|err| ParseError {
+            kind: ParseErrorKind::ExpectedValue,
+            ..err
+        })?;
+        let value = match token.kind {
+            TokenKind::Word(value) | TokenKind::Quoted(value) => value,
+            _ => {
+                return Err(ParseError {
+                    position: token.position,
+                    kind: ParseErrorKind::ExpectedValue,
+                })
+            }
+        };
+        let is_wildcard = self
+            .peek()
+            .is_some_and(|next| next.kind == TokenKind::Star && next.adjacent);
+        if is_wildcard {
+            self.position += 1;
+            Ok(Expr::Wildcard(value))
+        } else {
+            Ok(Expr::Value(value))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(input: &str) -> (usize, ParseErrorKind) {
+        let err = parse(input).unwrap_err();
+        (err.position, err.kind)
+    }
+
+    #[test]
+    fn parse_queries() {
+        let query = parse("status:open").unwrap();
+        assert_eq!(query.to_filter(), "status:open");
+        let query = parse("count >= 10").unwrap();
+        assert_eq!(query.to_filter(), "count>=10");
+        let query = parse(r#"message: "say \"hi\"""#).unwrap();
+        assert_eq!(query.to_filter(), r#"message:"say \"hi\"""#);
+        let query = parse("tag:crit*").unwrap();
+        assert_eq!(query.to_filter(), "tag:crit*");
+        let query = parse("priority=P1").unwrap();
+        assert_eq!(query.to_filter(), "priority:P1");
+    }
+
+    #[test]
+    fn parse_precedence() {
+        let expr = parse_expr("a:1 OR b:2 and NOT c:3").unwrap();
+        let query = |field: &str, value: &str| Expr::Query {
+            field: Field::from_name(field),
+            operator: Operator::ExactMatch,
+            filter: Box::new(Expr::Value(value.to_string())),
+        };
+        assert_eq!(
+            expr,
+            Expr::Or(
+                Box::new(query("a", "1")),
+                Box::new(Expr::And(
+                    Box::new(query("b", "2")),
+                    Box::new(Expr::Not(Box::new(query("c", "3"))))
+                ))
+            )
+        );
+
+        let expr = parse_expr("tag:(a OR b*)").unwrap();
+        assert_eq!(
+            expr,
+            Expr::Query {
+                field: Field::Tag,
+                operator: Operator::ExactMatch,
+                filter: Box::new(Expr::Or(
+                    Box::new(Expr::Value("a".to_string())),
+                    Box::new(Expr::Wildcard("b".to_string()))
+                )),
+            }
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let query = Query::new("field", "value")
+            .and(Query::less("field2", 42))
+            .or(Query::message("two words"));
+        let parsed = parse(&query.to_filter()).unwrap();
+        assert_eq!(parsed.to_filter(), query.to_filter());
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(error(""), (0, ParseErrorKind::UnexpectedEnd));
+        assert_eq!(error("status"), (6, ParseErrorKind::ExpectedOperator));
+        assert_eq!(error("status open"), (7, ParseErrorKind::ExpectedOperator));
+        assert_eq!(error("status:"), (7, ParseErrorKind::ExpectedValue));
+        assert_eq!(error("status:)"), (7, ParseErrorKind::ExpectedValue));
+        assert_eq!(error(":open"), (0, ParseErrorKind::ExpectedField));
+        assert_eq!(
+            error("message:\"open"),
+            (8, ParseErrorKind::UnterminatedQuote)
+        );
+        assert_eq!(
+            error("(status:open"),
+            (0, ParseErrorKind::UnclosedParenthesis)
+        );
+        assert_eq!(
+            error("status:open)"),
+            (11, ParseErrorKind::UnexpectedToken(")".to_string()))
+        );
+        assert_eq!(
+            error("status:open tag:a"),
+            (12, ParseErrorKind::UnexpectedToken("tag".to_string()))
+        );
+        assert_eq!(
+            parse("status:").unwrap_err().to_string(),
+            "expected a value at position 7"
+        );
+    }
+
+    #[test]
+    fn nesting_is_limited() {
+        let nested = |depth: usize| format!("{}a:b{}", "(".repeat(depth), ")".repeat(depth));
+        assert!(parse(&nested(MAX_NESTING)).is_ok());
+        assert_eq!(
+            error(&nested(MAX_NESTING + 1)),
+            (MAX_NESTING, ParseErrorKind::TooDeeplyNested)
+        );
+        assert_eq!(
+            error(&"(".repeat(100_000)),
+            (MAX_NESTING, ParseErrorKind::TooDeeplyNested)
+        );
+        assert_eq!(
+            error(&"NOT ".repeat(100_000)),
+            (MAX_NESTING * 4, ParseErrorKind::TooDeeplyNested)
+        );
+        assert_eq!(
+            error(&format!("tag:{}", "(".repeat(100_000))),
+            (4 + MAX_NESTING, ParseErrorKind::TooDeeplyNested)
+        );
+
+        let chain = |operators: usize| vec!["a:b"; operators + 1].join(" OR ");
+        assert!(parse(&chain(MAX_OPERATORS)).is_ok());
+        let err = parse(&chain(100_000)).unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::TooManyOperators);
+        assert_eq!(err.position, MAX_OPERATORS * 7 + 4);
+    }
+}
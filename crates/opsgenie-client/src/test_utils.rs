@@ -1,8 +1,18 @@
-use serde::de::DeserializeOwned;
+use std::path::Path;
 
-use crate::api::response::ApiResponse;
+use crate::{cassette::Cassette, OpsgenieClient};
 
-/// Checks whether the `ApiResponse<T>`` can be deserialized.
-pub fn response_test<T: DeserializeOwned>(fixture: &str) {
-    let _response: ApiResponse<T> = serde_json::from_str(fixture).expect("Unable to deserialize");
+/// Creates a client replaying the interactions from `cassettes/{name}.json`.
+pub fn replay_client(name: &str) -> OpsgenieClient {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("cassettes")
+        .join(format!("{name}.json"));
+    let cassette = Cassette::replay(&path)
+        .unwrap_or_else(|err| panic!("Unable to load cassette {}: {err}", path.display()));
+    OpsgenieClient::new(
+        "https://api.opsgenie.com".parse().unwrap(),
+        "api-key".into(),
+    )
+    .unwrap()
+    .with_cassette(cassette)
 }
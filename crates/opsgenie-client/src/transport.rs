@@ -0,0 +1,143 @@
+//! HTTP transport used by [`OpsgenieClient`](crate::OpsgenieClient).
+//!
+//! By default, requests are sent with [`reqwest`]. A custom [`Transport`] allows injecting mocks,
+//! adding middleware such as logging, metrics or caching, or sending requests through a custom
+//! HTTP stack. Middleware is implemented by wrapping another transport:
+//!
+//! ```
+//! use opsgenie_client::transport::{BoxFuture, Transport};
+//!
+//! #[derive(Debug)]
+//! struct Logging<T>(T);
+//!
+//! impl<T: Transport> Transport for Logging<T> {
+//!     fn execute(
+//!         &self,
+//!         request: reqwest::Request,
+//!     ) -> BoxFuture<'_, opsgenie_client::Result<reqwest::Response>> {
+//!         Box::pin(async move {
+//!             let (method, url) = (request.method().clone(), request.url().clone());
+//!             let response = self.0.execute(request).await?;
+//!             println!("{method} {url}: {}", response.status());
+//!             Ok(response)
+//!         })
+//!     }
+//! }
+//! ```
+
+use std::{fmt, future::Future, pin::Pin, sync::Arc};
+
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Sends HTTP requests.
+///
+/// Requests passed to the transport are complete, including the authentication header.
+/// Unsuccessful responses must be returned as-is rather than converted into errors,
+/// so that the client can report the error details returned by Opsgenie.
+pub trait Transport: fmt::Debug + Send + Sync + 'static {
+    fn execute(&self, request: reqwest::Request)
+        -> BoxFuture<'_, crate::Result<reqwest::Response>>;
+}
+
+impl<T: Transport + ?Sized> Transport for Arc<T> {
+    fn execute(
+        &self,
+        request: reqwest::Request,
+    ) -> BoxFuture<'_, crate::Result<reqwest::Response>> {
+        self.as_ref().execute(request)
+    }
+}
+
+impl<T: Transport + ?Sized> Transport for Box<T> {
+    fn execute(
+        &self,
+        request: reqwest::Request,
+    ) -> BoxFuture<'_, crate::Result<reqwest::Response>> {
+        self.as_ref().execute(request)
+    }
+}
+
+/// Transport sending requests with a [`reqwest::Client`].
+#[derive(Debug, Clone, Default)]
+pub struct ReqwestTransport {
+    client: reqwest::Client,
+}
+
+impl ReqwestTransport {
+    pub fn new(client: reqwest::Client) -> Self {
+        Self { client }
+    }
+}
+
+impl Transport for ReqwestTransport {
+    fn execute(
+        &self,
+        request: reqwest::Request,
+    ) -> BoxFuture<'_, crate::Result<reqwest::Response>> {
+        Box::pin(async move { Ok(self.client.execute(request).await?) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::{
+        query_builder::{AlertStatus, Query},
+        OpsgenieClient,
+    };
+
+    /// Responds with the same body to all requests, keeping the requests.
+    #[derive(Debug, Default)]
+    struct StaticTransport {
+        body: &'static str,
+        requests: Mutex<Vec<(String, String, Option<String>)>>,
+    }
+
+    impl Transport for StaticTransport {
+        fn execute(
+            &self,
+            request: reqwest::Request,
+        ) -> BoxFuture<'_, crate::Result<reqwest::Response>> {
+            let authorization = request
+                .headers()
+                .get(http::header::AUTHORIZATION)
+                .map(|value| value.to_str().unwrap().to_owned());
+            self.requests.lock().unwrap().push((
+                request.method().to_string(),
+                request.url().to_string(),
+                authorization,
+            ));
+            let response = http::Response::builder().body(self.body).unwrap();
+            Box::pin(async move { Ok(response.into()) })
+        }
+    }
+
+    #[tokio::test]
+    async fn custom_transport() {
+        let transport = Arc::new(StaticTransport {
+            body: r#"{"data": {"count": 7}, "took": 0.05, "requestId": "id"}"#,
+            ..StaticTransport::default()
+        });
+        let client = OpsgenieClient::new("https://api.opsgenie.com".parse().unwrap(), "key".into())
+            .unwrap()
+            .with_transport(transport.clone());
+        let count = client
+            .alert()
+            .count(Query::status(AlertStatus::Open))
+            .await
+            .unwrap();
+        assert_eq!(count.data.count, 7);
+
+        let requests = transport.requests.lock().unwrap();
+        assert_eq!(
+            *requests,
+            [(
+                "GET".to_string(),
+                "https://api.opsgenie.com/v2/alerts/count?query=status%3Aopen".to_string(),
+                Some("GenieKey key".to_string())
+            )]
+        );
+    }
+}
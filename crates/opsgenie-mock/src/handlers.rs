@@ -0,0 +1,429 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use chrono::Utc;
+use opsgenie_client::{
+    api::{
+        alert::response::{Alert, Count, Responder},
+        on_call::response::OnCallRecipients,
+        team::response::TeamDescriptor,
+    },
+    query_builder::{AlertMatcher, ParseError},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::Shared;
+
+/// Time reported in the `took` field of the responses.
+const TOOK: f64 = 0.001;
+/// Default number of alerts or incidents returned by the list requests.
+const DEFAULT_LIST_LIMIT: usize = 20;
+/// Maximum number of alerts or incidents returned by the list requests.
+const MAX_LIST_LIMIT: usize = 100;
+
+pub(crate) fn router() -> Router<Arc<Shared>> {
+    Router::new()
+        .route("/v2/account", get(account))
+        .route("/v2/teams", get(list_teams))
+        .route("/v2/teams/:id", get(get_team))
+        .route("/v2/schedules", get(list_schedules))
+        .route("/v2/schedules/:id/on-calls", get(on_calls))
+        .route("/v2/alerts", get(list_alerts).post(create_alert))
+        .route("/v2/alerts/count", get(count_alerts))
+        .route("/v2/alerts/:identifier/close", post(close_alert))
+        .route("/v1/incidents", get(list_incidents))
+        .route("/v2/forwarding-rules", get(list_forwarding_rules))
+        .route("/v1/services", get(list_services))
+        .route("/v2/deployments", get(list_deployments))
+        .route("/v2/heartbeats/:name/ping", get(ping_heartbeat))
+        .fallback(not_found)
+}
+
+/// Successful response with data.
+fn data<T: Serialize>(shared: &Shared, data: T) -> Response {
+    Json(json!({
+        "data": data,
+        "took": TOOK,
+        "requestId": shared.next_request_id(),
+    }))
+    .into_response()
+}
+
+/// Successful response without data.
+fn result(shared: &Shared, status: StatusCode, result: &str) -> Response {
+    let body = json!({
+        "result": result,
+        "took": TOOK,
+        "requestId": shared.next_request_id(),
+    });
+    (status, Json(body)).into_response()
+}
+
+pub(crate) fn error(shared: &Shared, status: StatusCode, message: &str) -> Response {
+    let body = json!({
+        "message": message,
+        "took": TOOK,
+        "requestId": shared.next_request_id(),
+    });
+    (status, Json(body)).into_response()
+}
+
+async fn not_found(State(shared): State<Arc<Shared>>) -> Response {
+    error(&shared, StatusCode::NOT_FOUND, "Not found")
+}
+
+async fn account(State(shared): State<Arc<Shared>>) -> Response {
+    let account = shared.state().account.clone();
+    data(&shared, account)
+}
+
+async fn list_teams(State(shared): State<Arc<Shared>>) -> Response {
+    let teams: Vec<_> = shared
+        .state()
+        .teams
+        .iter()
+        .map(|team| TeamDescriptor {
+            id: team.id.clone(),
+            description: team.description.clone(),
+            name: team.name.clone(),
+        })
+        .collect();
+    data(&shared, teams)
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct IdentifierParams {
+    identifier_type: Option<String>,
+}
+
+async fn get_team(
+    State(shared): State<Arc<Shared>>,
+    Path(identifier): Path<String>,
+    Query(params): Query<IdentifierParams>,
+) -> Response {
+    let by_name = params.identifier_type.as_deref() == Some("name");
+    let team = shared
+        .state()
+        .teams
+        .iter()
+        .find(|team| {
+            if by_name {
+                team.name == identifier
+            } else {
+                team.id == identifier
+            }
+        })
+        .cloned();
+    match team {
+        Some(team) => data(&shared, team),
+        None => error(
+            &shared,
+            StatusCode::NOT_FOUND,
+            &format!("No team exists with identifier [{identifier}]"),
+        ),
+    }
+}
+
+async fn list_schedules(State(shared): State<Arc<Shared>>) -> Response {
+    let schedules = shared.state().schedules.clone();
+    data(&shared, schedules)
+}
+
+async fn on_calls(State(shared): State<Arc<Shared>>, Path(id): Path<String>) -> Response {
+    let recipients = {
+        let state = shared.state();
+        if !state.schedules.iter().any(|schedule| schedule.id == id) {
+            None
+        } else {
+            Some(state.on_calls.get(&id).cloned().unwrap_or_default())
+        }
+    };
+    match recipients {
+        Some(on_call_recipients) => data(&shared, OnCallRecipients { on_call_recipients }),
+        None => error(
+            &shared,
+            StatusCode::NOT_FOUND,
+            &format!("No schedule exists with identifier [{id}]"),
+        ),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct AlertParams {
+    query: Option<String>,
+    limit: Option<usize>,
+    offset: Option<usize>,
+    sort: Option<String>,
+    order: Option<String>,
+}
+
+/// Returns the alerts matching the search query. Empty query matches all alerts.
+fn find_alerts(shared: &Shared, query: Option<&str>) -> Result<Vec<Alert>, ParseError> {
+    let matcher = query
+        .filter(|query| !query.trim().is_empty())
+        .map(AlertMatcher::parse)
+        .transpose()?;
+    Ok(shared
+        .state()
+        .alerts
+        .iter()
+        .filter(|alert| {
+            matcher
+                .as_ref()
+                .is_none_or(|matcher| matcher.matches(alert))
+        })
+        .cloned()
+        .collect())
+}
+
+fn invalid_query(shared: &Shared, err: &ParseError) -> Response {
+    error(
+        shared,
+        StatusCode::UNPROCESSABLE_ENTITY,
+        &format!("Invalid query: {err}"),
+    )
+}
+
+async fn count_alerts(
+    State(shared): State<Arc<Shared>>,
+    Query(params): Query<AlertParams>,
+) -> Response {
+    match find_alerts(&shared, params.query.as_deref()) {
+        Ok(alerts) => data(
+            &shared,
+            Count {
+                count: alerts.len() as u64,
+            },
+        ),
+        Err(err) => invalid_query(&shared, &err),
+    }
+}
+
+async fn list_alerts(
+    State(shared): State<Arc<Shared>>,
+    Query(params): Query<AlertParams>,
+) -> Response {
+    let mut alerts = match find_alerts(&shared, params.query.as_deref()) {
+        Ok(alerts) => alerts,
+        Err(err) => return invalid_query(&shared, &err),
+    };
+    // Alerts are sorted by creation time, most recent first by default.
+    match params.sort.as_deref() {
+        Some("updatedAt") => alerts.sort_by_key(|alert| alert.updated_at),
+        _ => alerts.sort_by_key(|alert| alert.created_at),
+    }
+    if params.order.as_deref() != Some("asc") {
+        alerts.reverse();
+    }
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_LIST_LIMIT)
+        .min(MAX_LIST_LIMIT);
+    let alerts: Vec<_> = alerts
+        .into_iter()
+        .skip(params.offset.unwrap_or(0))
+        .take(limit)
+        .collect();
+    data(&shared, alerts)
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateAlert {
+    message: String,
+    alias: Option<String>,
+    #[serde(default)]
+    responders: Vec<CreateResponder>,
+    #[serde(default)]
+    tags: Vec<String>,
+    source: Option<String>,
+    priority: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CreateResponder {
+    r#type: String,
+    id: Option<String>,
+    name: Option<String>,
+    username: Option<String>,
+}
+
+async fn create_alert(
+    State(shared): State<Arc<Shared>>,
+    Json(request): Json<CreateAlert>,
+) -> Response {
+    {
+        let mut state = shared.state();
+        let now = Utc::now().fixed_offset();
+        let existing = request.alias.as_ref().and_then(|alias| {
+            state
+                .alerts
+                .iter_mut()
+                .find(|alert| alert.status == "open" && alert.alias.as_ref() == Some(alias))
+        });
+        if let Some(alert) = existing {
+            // Opsgenie deduplicates open alerts with the same alias.
+            alert.count += 1;
+            alert.last_occurred_at = now;
+            alert.updated_at = now;
+        } else {
+            let responders: Vec<_> = request
+                .responders
+                .into_iter()
+                .map(|responder| {
+                    let team = (responder.r#type == "team")
+                        .then(|| {
+                            state.teams.iter().find(|team| {
+                                Some(&team.id) == responder.id.as_ref()
+                                    || Some(&team.name) == responder.name.as_ref()
+                            })
+                        })
+                        .flatten();
+                    let id = team
+                        .map(|team| team.id.clone())
+                        .or(responder.id)
+                        .unwrap_or_default();
+                    Responder {
+                        id,
+                        r#type: responder.r#type,
+                        name: team.map(|team| team.name.clone()).or(responder.name),
+                        username: responder.username,
+                    }
+                })
+                .collect();
+            let alert = state.add_alert(&request.message);
+            alert.alias = request.alias;
+            alert.tags = Some(request.tags);
+            alert.responders = responders;
+            if let Some(source) = request.source {
+                alert.source = source;
+            }
+            if let Some(priority) = request.priority {
+                alert.priority = priority;
+            }
+        }
+    }
+    result(&shared, StatusCode::ACCEPTED, "Request will be processed")
+}
+
+async fn close_alert(
+    State(shared): State<Arc<Shared>>,
+    Path(identifier): Path<String>,
+    Query(params): Query<IdentifierParams>,
+) -> Response {
+    {
+        let mut state = shared.state();
+        let identifier_type = params.identifier_type.as_deref().unwrap_or("id");
+        let alert = state.alerts.iter_mut().find(|alert| match identifier_type {
+            "tiny" => alert.tiny_id == identifier,
+            // Only open alerts are deduplicated by their alias.
+            "alias" => alert.status == "open" && alert.alias.as_ref() == Some(&identifier),
+            _ => alert.id == identifier,
+        });
+        // Opsgenie processes the request asynchronously, so unknown alerts are not reported.
+        if let Some(alert) = alert {
+            alert.status = "closed".into();
+            alert.updated_at = Utc::now().fixed_offset();
+        }
+    }
+    result(&shared, StatusCode::ACCEPTED, "Request will be processed")
+}
+
+/// Lists the incidents, most recent first. Only `status:<status>` queries are supported.
+async fn list_incidents(
+    State(shared): State<Arc<Shared>>,
+    Query(params): Query<AlertParams>,
+) -> Response {
+    let query = params.query.as_deref().unwrap_or_default().trim();
+    let status = match query.strip_prefix("status:") {
+        Some(status) => Some(status),
+        None if query.is_empty() => None,
+        None => {
+            let message = format!("Unsupported query: {query}");
+            return error(&shared, StatusCode::UNPROCESSABLE_ENTITY, &message);
+        }
+    };
+    let mut incidents: Vec<_> = shared
+        .state()
+        .incidents
+        .iter()
+        .filter(|incident| status.is_none_or(|status| incident.status == status))
+        .cloned()
+        .collect();
+    incidents.sort_by_key(|incident| incident.created_at);
+    incidents.reverse();
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_LIST_LIMIT)
+        .min(MAX_LIST_LIMIT);
+    let incidents: Vec<_> = incidents
+        .into_iter()
+        .skip(params.offset.unwrap_or(0))
+        .take(limit)
+        .collect();
+    data(&shared, incidents)
+}
+
+async fn list_forwarding_rules(State(shared): State<Arc<Shared>>) -> Response {
+    let rules = shared.state().forwarding_rules.clone();
+    data(&shared, rules)
+}
+
+async fn list_services(State(shared): State<Arc<Shared>>) -> Response {
+    let services = shared.state().services.clone();
+    data(&shared, services)
+}
+
+#[derive(Debug, Deserialize)]
+struct PageParams {
+    limit: Option<usize>,
+    offset: Option<usize>,
+}
+
+/// Returns the deployments, newest first.
+async fn list_deployments(
+    State(shared): State<Arc<Shared>>,
+    Query(params): Query<PageParams>,
+) -> Response {
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_LIST_LIMIT)
+        .min(MAX_LIST_LIMIT);
+    let deployments: Vec<_> = shared
+        .state()
+        .deployments
+        .iter()
+        .rev()
+        .skip(params.offset.unwrap_or(0))
+        .take(limit)
+        .cloned()
+        .collect();
+    data(&shared, deployments)
+}
+
+async fn ping_heartbeat(State(shared): State<Arc<Shared>>, Path(name): Path<String>) -> Response {
+    let found = match shared.state().heartbeat_pings.get_mut(&name) {
+        Some(pings) => {
+            *pings += 1;
+            true
+        }
+        None => false,
+    };
+    if found {
+        result(&shared, StatusCode::ACCEPTED, "PONG - Heartbeat received")
+    } else {
+        error(
+            &shared,
+            StatusCode::NOT_FOUND,
+            &format!("No heartbeat found with name [{name}]"),
+        )
+    }
+}
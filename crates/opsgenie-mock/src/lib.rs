@@ -0,0 +1,419 @@
+//! In-process mock of the Opsgenie REST API.
+//!
+//! The mock implements the subset of endpoints used by `opsgenie-client` on top of
+//! in-memory [`MockState`], so that the client and its users can be tested end-to-end
+//! without network access. Failures such as rate limiting can be injected with
+//! [`MockServer::fail_next`] and [`MockServer::fail_path`].
+
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, MutexGuard,
+    },
+};
+
+use axum::{
+    extract::{Request, State},
+    http::{header, StatusCode},
+    middleware::{self, Next},
+    response::Response,
+};
+use opsgenie_client::OpsgenieClient;
+use tokio::{net::TcpListener, task::JoinHandle};
+use url::Url;
+
+pub use crate::state::MockState;
+
+mod handlers;
+mod state;
+
+/// API key accepted by the mock server.
+pub const API_KEY: &str = "mock-api-key";
+
+/// Failure that can be injected into the responses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Failure {
+    /// `429 Too Many Requests`, returned when the rate limit is exceeded.
+    RateLimited,
+    /// `503 Service Unavailable`.
+    Unavailable,
+}
+
+/// Request received by the mock server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub path: String,
+    pub query: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct Shared {
+    state: Mutex<MockState>,
+    /// Injected failures with the path of the requests they apply to, or `None` for any request.
+    failures: Mutex<VecDeque<(Option<String>, Failure)>>,
+    requests: Mutex<Vec<RecordedRequest>>,
+    request_counter: AtomicU64,
+}
+
+impl Shared {
+    fn state(&self) -> MutexGuard<'_, MockState> {
+        self.state.lock().unwrap()
+    }
+
+    fn next_request_id(&self) -> String {
+        let id = self.request_counter.fetch_add(1, Ordering::Relaxed);
+        format!("mock-request-{id}")
+    }
+}
+
+/// Mock Opsgenie server listening on a random local port.
+/// The server is stopped when dropped.
+#[derive(Debug)]
+pub struct MockServer {
+    url: Url,
+    shared: Arc<Shared>,
+    server: JoinHandle<()>,
+}
+
+impl MockServer {
+    /// Starts the server in the background. Must be called within a Tokio runtime.
+    pub async fn start(state: MockState) -> Self {
+        let shared = Arc::new(Shared {
+            state: Mutex::new(state),
+            ..Shared::default()
+        });
+        let app = handlers::router()
+            .layer(middleware::from_fn_with_state(shared.clone(), intercept))
+            .with_state(shared.clone());
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("Failed to bind mock server");
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            if let Err(err) = axum::serve(listener, app).await {
+                tracing::error!("Mock server failed: {}", err);
+            }
+        });
+        Self {
+            url: format!("http://{addr}/").parse().unwrap(),
+            shared,
+            server,
+        }
+    }
+
+    /// Base URL of the server, to be passed to [`OpsgenieClient::new`].
+    pub fn url(&self) -> Url {
+        self.url.clone()
+    }
+
+    /// Creates a client connected to the server.
+    pub fn client(&self) -> OpsgenieClient {
+        OpsgenieClient::new(self.url(), API_KEY.to_string()).expect("Invalid mock server URL")
+    }
+
+    /// Provides access to the served data.
+    /// The guard must not be held while requests are performed.
+    pub fn state(&self) -> MutexGuard<'_, MockState> {
+        self.shared.state()
+    }
+
+    /// Makes the next `count` requests fail.
+    pub fn fail_next(&self, failure: Failure, count: usize) {
+        let mut failures = self.shared.failures.lock().unwrap();
+        failures.extend(std::iter::repeat_n((None, failure), count));
+    }
+
+    /// Makes the next `count` requests to the path fail, e.g. `/v2/teams`.
+    /// Requests to other paths are not affected.
+    pub fn fail_path(&self, path: &str, failure: Failure, count: usize) {
+        let mut failures = self.shared.failures.lock().unwrap();
+        failures.extend(std::iter::repeat_n(
+            (Some(path.to_string()), failure),
+            count,
+        ));
+    }
+
+    /// Returns all requests received so far, including the failed ones.
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.shared.requests.lock().unwrap().clone()
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+/// Records requests, checks authentication and injects failures.
+async fn intercept(State(shared): State<Arc<Shared>>, request: Request, next: Next) -> Response {
+    shared.requests.lock().unwrap().push(RecordedRequest {
+        method: request.method().to_string(),
+        path: request.uri().path().to_string(),
+        query: request.uri().query().map(str::to_owned),
+    });
+
+    let failure = {
+        let mut failures = shared.failures.lock().unwrap();
+        failures
+            .iter()
+            .position(|(path, _)| {
+                path.as_deref()
+                    .is_none_or(|path| path == request.uri().path())
+            })
+            .and_then(|position| failures.remove(position))
+            .map(|(_, failure)| failure)
+    };
+    match failure {
+        Some(Failure::RateLimited) => {
+            let mut response = handlers::error(
+                &shared,
+                StatusCode::TOO_MANY_REQUESTS,
+                "You are making too many requests! To avoid errors, we recommend you limit requests.",
+            );
+            response
+                .headers_mut()
+                .insert("X-RateLimit-State", "THROTTLED".parse().unwrap());
+            return response;
+        }
+        Some(Failure::Unavailable) => {
+            return handlers::error(
+                &shared,
+                StatusCode::SERVICE_UNAVAILABLE,
+                "Service unavailable",
+            );
+        }
+        None => {}
+    }
+
+    let authorization = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok());
+    if authorization != Some(&format!("GenieKey {API_KEY}")) {
+        return handlers::error(&shared, StatusCode::UNAUTHORIZED, "Could not authenticate");
+    }
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use opsgenie_client::{
+        api::alert::request::{self, CreateAlert},
+        cassette::Cassette,
+        pagination::{Order, Pagination},
+        query_builder::{AlertStatus, Priority, Query, ToFilter as _},
+        ClientError,
+    };
+
+    use super::*;
+
+    fn state() -> MockState {
+        let mut state = MockState::new();
+        let ops = state.add_team("ops", &["neo@example.com", "trinity@example.com"]);
+        let dev = state.add_team("dev", &["morpheus@example.com"]);
+        let schedule = state.add_schedule("ops_schedule", &ops);
+        state.set_on_call(&schedule, &["neo@example.com"]);
+        state.add_schedule("dev_schedule", &dev);
+
+        let ops_responder = state.team_responder(&ops);
+        let alert = state.add_alert("Disk is full");
+        alert.priority = "P1".into();
+        alert.responders.push(ops_responder.clone());
+        let alert = state.add_alert("Disk is almost full");
+        alert.status = "closed".into();
+        alert.responders.push(ops_responder);
+        let dev_responder = state.team_responder(&dev);
+        state
+            .add_alert("Build failed")
+            .responders
+            .push(dev_responder);
+        state
+    }
+
+    #[tokio::test]
+    async fn teams_and_schedules() {
+        let server = MockServer::start(state()).await;
+        let client = server.client();
+
+        let teams = client.team().list_teams().await.unwrap().data;
+        let names: Vec<_> = teams.iter().map(|team| team.name.as_str()).collect();
+        assert_eq!(names, ["ops", "dev"]);
+        let team = client.team().get(teams[0].id.clone()).await.unwrap().data;
+        assert_eq!(team.members.unwrap().len(), 2);
+
+        let schedules = client.schedule().list_schedules().await.unwrap().data;
+        assert_eq!(schedules.len(), 2);
+        assert_eq!(schedules[0].owner_team.name, "ops");
+        let on_call = client
+            .on_call()
+            .whoisoncall(&schedules[0].id)
+            .await
+            .unwrap();
+        assert_eq!(on_call.data.on_call_recipients, ["neo@example.com"]);
+        let on_call = client
+            .on_call()
+            .whoisoncall(&schedules[1].id)
+            .await
+            .unwrap();
+        assert!(on_call.data.on_call_recipients.is_empty());
+
+        let err = client.team().get("unknown".into()).await.unwrap_err();
+        assert!(matches!(err, ClientError::Request(_)), "{err:?}");
+    }
+
+    #[tokio::test]
+    async fn alert_queries() {
+        let server = MockServer::start(state()).await;
+        let client = server.client();
+
+        let count = client
+            .alert()
+            .count(Query::new("team", "ops"))
+            .await
+            .unwrap();
+        assert_eq!(count.data.count, 2);
+        let query = Query::new("team", "ops").and(Query::status(AlertStatus::Open));
+        let count = client.alert().count(query).await.unwrap();
+        assert_eq!(count.data.count, 1);
+        let query = Query::priority(Priority::P1).or(Query::message("build"));
+        let alerts = client.alert().list(query, None).await.unwrap().data;
+        let messages: Vec<_> = alerts.iter().map(|alert| alert.message.as_str()).collect();
+        assert_eq!(messages, ["Build failed", "Disk is full"]);
+        let alerts = client.alert().list(Query::tag("*"), Some(1)).await.unwrap();
+        assert!(alerts.data.is_empty());
+
+        let pagination = Pagination::new()
+            .with_limit(1)
+            .with_offset(1)
+            .with_sort("updatedAt".to_string())
+            .with_order(Order::Asc);
+        let alerts = client
+            .alert()
+            .list_page(Query::new("team", "ops"), &pagination)
+            .await
+            .unwrap();
+        assert_eq!(alerts.data.len(), 1);
+        assert_eq!(alerts.data[0].message, "Disk is almost full");
+        let request = server.requests().pop().unwrap();
+        assert_eq!(
+            request.query.as_deref(),
+            Some("query=team%3Aops&offset=1&limit=1&sort=updatedAt&order=asc")
+        );
+    }
+
+    #[tokio::test]
+    async fn create_alert() {
+        let server = MockServer::start(MockState::new()).await;
+        server.state().add_team("ops", &[]);
+        let client = server.client();
+
+        let mut alert = CreateAlert::new("Exporter failed");
+        alert.alias = Some("failure".into());
+        alert.responders = vec![request::Responder::team("ops")];
+        client.alert().create(&alert).await.unwrap();
+        client.alert().create(&alert).await.unwrap();
+
+        {
+            let state = server.state();
+            assert_eq!(state.alerts.len(), 1);
+            assert_eq!(state.alerts[0].count, 2);
+            assert_eq!(state.alerts[0].responders[0].id, state.teams[0].id);
+        }
+        let filter = Query::alias("failure").and(Query::new("team", "ops"));
+        let count = client.alert().count(filter).await.unwrap();
+        assert_eq!(count.data.count, 1);
+    }
+
+    #[tokio::test]
+    async fn injected_failures() {
+        let server = MockServer::start(state()).await;
+        let client = server.client();
+
+        server.fail_next(Failure::RateLimited, 1);
+        server.fail_next(Failure::Unavailable, 1);
+        let err = client.team().list_teams().await.unwrap_err();
+        assert!(
+            matches!(&err, ClientError::Request(err) if err.message.contains("too many requests")),
+            "{err:?}"
+        );
+        let err = client.team().list_teams().await.unwrap_err();
+        assert!(matches!(err, ClientError::Request(_)), "{err:?}");
+        client.team().list_teams().await.unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 3);
+        assert!(requests.iter().all(|request| request.path == "/v2/teams"));
+
+        server.fail_path("/v2/account", Failure::Unavailable, 1);
+        client.team().list_teams().await.unwrap();
+        client.account().get().await.unwrap_err();
+        client.account().get().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn authentication() {
+        let server = MockServer::start(state()).await;
+        let client = OpsgenieClient::new(server.url(), "wrong".into()).unwrap();
+        let err = client.account().get().await.unwrap_err();
+        assert!(matches!(err, ClientError::Request(_)), "{err:?}");
+        let account = server.client().account().get().await.unwrap();
+        assert_eq!(account.data.user_count, 3);
+    }
+
+    #[tokio::test]
+    async fn record_and_replay() {
+        let path = std::env::temp_dir()
+            .join(format!("opsgenie-mock-{}", std::process::id()))
+            .join("cassette.json");
+        let server = MockServer::start(state()).await;
+        let client = server.client().with_cassette(Cassette::record(&path));
+        let teams = client.team().list_teams().await.unwrap().data;
+        let count = client
+            .alert()
+            .count(Query::new("team", "ops"))
+            .await
+            .unwrap();
+        drop(server);
+
+        let cassette = Cassette::replay(&path).unwrap();
+        let interactions = cassette.interactions();
+        assert_eq!(interactions.len(), 2);
+        assert_eq!(
+            interactions[0].request.headers["authorization"],
+            "GenieKey [REDACTED]"
+        );
+        let recorded = std::fs::read_to_string(&path).unwrap();
+        assert!(!recorded.contains(API_KEY));
+
+        let client = OpsgenieClient::new(server_url(), "other".into())
+            .unwrap()
+            .with_cassette(cassette);
+        let replayed_teams = client.team().list_teams().await.unwrap().data;
+        assert_eq!(replayed_teams.len(), teams.len());
+        let replayed_count = client
+            .alert()
+            .count(Query::new("team", "ops"))
+            .await
+            .unwrap();
+        assert_eq!(replayed_count.data.count, count.data.count);
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    /// URL that doesn't accept connections.
+    fn server_url() -> Url {
+        "http://127.0.0.1:1/".parse().unwrap()
+    }
+
+    #[tokio::test]
+    async fn heartbeats() {
+        let server = MockServer::start(MockState::new()).await;
+        server.state().add_heartbeat("exporter");
+        let client = server.client();
+        client.heartbeat().ping("exporter").await.unwrap();
+        assert!(client.heartbeat().ping("unknown").await.is_err());
+        assert_eq!(server.state().heartbeat_pings["exporter"], 1);
+    }
+}
@@ -0,0 +1,264 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, FixedOffset, Utc};
+use opsgenie_client::api::{
+    account::response::{Account, Plan},
+    alert::response::{Alert, Responder},
+    deployment::response::{Deployment, DeploymentState, Environment},
+    forwarding_rule::response::ForwardingRule,
+    incident::response::Incident,
+    schedule::response::Schedule,
+    service::response::Service,
+    team::response::{Team, TeamMember, User},
+};
+
+/// Data served by the mock server.
+///
+/// Fields can be modified directly. Helper methods generate IDs and keep the references
+/// between entities consistent.
+#[derive(Debug, Clone)]
+pub struct MockState {
+    pub account: Account,
+    pub teams: Vec<Team>,
+    pub schedules: Vec<Schedule>,
+    /// Usernames of the on-call participants by schedule ID.
+    pub on_calls: HashMap<String, Vec<String>>,
+    pub alerts: Vec<Alert>,
+    pub incidents: Vec<Incident>,
+    pub forwarding_rules: Vec<ForwardingRule>,
+    pub services: Vec<Service>,
+    /// Deployments, served from the most recent one.
+    pub deployments: Vec<Deployment>,
+    /// Number of pings received by each heartbeat.
+    /// Pinging a heartbeat that is not present here fails.
+    pub heartbeat_pings: HashMap<String, u64>,
+    next_id: u64,
+}
+
+impl Default for MockState {
+    fn default() -> Self {
+        Self {
+            account: Account {
+                name: "mock".into(),
+                user_count: 0,
+                plan: Plan {
+                    max_user_count: 1000,
+                    name: "Enterprise".into(),
+                    is_yearly: true,
+                },
+            },
+            teams: Vec::new(),
+            schedules: Vec::new(),
+            on_calls: HashMap::new(),
+            alerts: Vec::new(),
+            incidents: Vec::new(),
+            forwarding_rules: Vec::new(),
+            services: Vec::new(),
+            deployments: Vec::new(),
+            heartbeat_pings: HashMap::new(),
+            next_id: 1,
+        }
+    }
+}
+
+impl MockState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Generates a unique identifier formatted as a UUID.
+    pub fn next_id(&mut self) -> String {
+        let id = self.next_id;
+        self.next_id += 1;
+        format!("00000000-0000-4000-8000-{id:012x}")
+    }
+
+    /// Adds a team with the given members and returns its ID.
+    pub fn add_team(&mut self, name: &str, usernames: &[&str]) -> String {
+        let id = self.next_id();
+        let members = usernames
+            .iter()
+            .map(|username| TeamMember {
+                user: User {
+                    id: Some(self.next_id()),
+                    username: Some(username.to_string()),
+                },
+                role: "user".into(),
+            })
+            .collect();
+        self.account.user_count += usernames.len() as u64;
+        self.teams.push(Team {
+            id: id.clone(),
+            description: None,
+            name: name.into(),
+            members: Some(members),
+        });
+        id
+    }
+
+    pub fn team(&self, id: &str) -> Option<&Team> {
+        self.teams.iter().find(|team| team.id == id)
+    }
+
+    /// Adds a schedule owned by the team and returns its ID.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the team doesn't exist.
+    pub fn add_schedule(&mut self, name: &str, team_id: &str) -> String {
+        let team = self
+            .team(team_id)
+            .unwrap_or_else(|| panic!("Unknown team {team_id}"));
+        let owner_team = Team {
+            members: None,
+            ..team.clone()
+        };
+        let id = self.next_id();
+        self.schedules.push(Schedule {
+            id: id.clone(),
+            name: name.into(),
+            owner_team,
+        });
+        id
+    }
+
+    pub fn set_on_call(&mut self, schedule_id: &str, usernames: &[&str]) {
+        self.on_calls.insert(
+            schedule_id.into(),
+            usernames.iter().map(ToString::to_string).collect(),
+        );
+    }
+
+    /// Adds an open `P3` alert created now, and returns it for further changes.
+    pub fn add_alert(&mut self, message: &str) -> &mut Alert {
+        let id = self.next_id();
+        let now = Utc::now().fixed_offset();
+        let tiny_id = (self.alerts.len() + 1).to_string();
+        self.alerts.push(Alert {
+            id,
+            tiny_id,
+            alias: None,
+            message: message.into(),
+            status: "open".into(),
+            acknowledged: false,
+            is_seen: false,
+            tags: Some(Vec::new()),
+            snoozed: false,
+            snoozed_until: None,
+            count: 1,
+            last_occurred_at: now,
+            created_at: now,
+            updated_at: now,
+            source: "mock".into(),
+            owner: None,
+            priority: "P3".into(),
+            responders: Vec::new(),
+            report: None,
+        });
+        self.alerts.last_mut().unwrap()
+    }
+
+    /// Returns the responder referring to the team.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the team doesn't exist.
+    pub fn team_responder(&self, team_id: &str) -> Responder {
+        let team = self
+            .team(team_id)
+            .unwrap_or_else(|| panic!("Unknown team {team_id}"));
+        Responder {
+            id: team.id.clone(),
+            r#type: "team".into(),
+            name: Some(team.name.clone()),
+            username: None,
+        }
+    }
+
+    /// Adds a rule forwarding notifications between users and returns its ID.
+    pub fn add_forwarding_rule(
+        &mut self,
+        from_username: &str,
+        to_username: &str,
+        start_date: DateTime<FixedOffset>,
+        end_date: DateTime<FixedOffset>,
+    ) -> String {
+        let id = self.next_id();
+        let user = |username: &str| User {
+            id: None,
+            username: Some(username.into()),
+        };
+        self.forwarding_rules.push(ForwardingRule {
+            id: id.clone(),
+            alias: None,
+            from_user: user(from_username),
+            to_user: user(to_username),
+            start_date,
+            end_date,
+        });
+        id
+    }
+
+    /// Adds a service owned by the team and returns its ID.
+    pub fn add_service(&mut self, name: &str, team_id: &str) -> String {
+        let id = self.next_id();
+        self.services.push(Service {
+            id: id.clone(),
+            name: name.into(),
+            description: None,
+            team_id: team_id.into(),
+            visibility: None,
+            tags: None,
+            links: None,
+            is_external: None,
+        });
+        id
+    }
+
+    /// Adds an open P3 incident impacting the services, and returns it for further changes.
+    pub fn add_incident(&mut self, message: &str, service_ids: &[&str]) -> &mut Incident {
+        let id = self.next_id();
+        let now = Utc::now().fixed_offset();
+        let tiny_id = (self.incidents.len() + 1).to_string();
+        self.incidents.push(Incident {
+            id,
+            tiny_id,
+            message: message.into(),
+            description: None,
+            status: "open".into(),
+            tags: Vec::new(),
+            created_at: now,
+            updated_at: now,
+            priority: "P3".into(),
+            owner_team: None,
+            responders: Vec::new(),
+            impacted_services: service_ids.iter().map(ToString::to_string).collect(),
+        });
+        self.incidents.last_mut().unwrap()
+    }
+
+    /// Adds a production deployment of the services started now, and returns it for further changes.
+    pub fn add_deployment(&mut self, message: &str, service_ids: &[&str]) -> &mut Deployment {
+        let id = self.next_id();
+        self.deployments.push(Deployment {
+            id,
+            message: message.into(),
+            description: None,
+            state: DeploymentState::Started,
+            environment: Environment {
+                r#type: "production".into(),
+                id: "production".into(),
+            },
+            service_ids: service_ids.iter().map(ToString::to_string).collect(),
+            link: None,
+            source: None,
+            created_at: Utc::now().fixed_offset(),
+            updated_at: None,
+        });
+        self.deployments.last_mut().unwrap()
+    }
+
+    pub fn add_heartbeat(&mut self, name: &str) {
+        self.heartbeat_pings.insert(name.into(), 0);
+    }
+}
@@ -1,7 +1,44 @@
-use std::{io::BufRead as _, time::Duration};
+use std::{io::BufRead as _, sync::Arc, time::Duration};
 
 use anyhow::Context as _;
-use serde::Deserialize;
+use opsgenie_client::query_builder::{self, Precedence, ToFilter};
+use serde::{de::Error as _, Deserialize, Deserializer};
+
+use crate::{limiter::DomainLimits, updater::DataDomain};
+
+/// How the metrics are collected from Opsgenie.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum CollectionMode {
+    /// Metrics are updated in the background on the polling intervals.
+    #[default]
+    Polling,
+    /// Metrics are updated when they are scraped, once they are older than the polling intervals.
+    Scrape,
+}
+
+/// Opsgenie search query parsed from the configuration, reused in multiple queries.
+#[derive(Debug, Clone)]
+pub(crate) struct AlertFilter(Arc<dyn ToFilter>);
+
+impl<'de> Deserialize<'de> for AlertFilter {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let query = String::deserialize(deserializer)?;
+        let filter = query_builder::parse(&query)
+            .map_err(|err| D::Error::custom(format!("invalid ALERT_FILTER: {err}")))?;
+        Ok(Self(filter.into()))
+    }
+}
+
+impl ToFilter for AlertFilter {
+    fn to_filter(&self) -> String {
+        self.0.to_filter()
+    }
+
+    fn precedence(&self) -> Precedence {
+        self.0.precedence()
+    }
+}
 
 #[derive(Debug, Deserialize)]
 pub(crate) struct Config {
@@ -11,14 +48,67 @@ pub(crate) struct Config {
     pub prometheus_port: u16,
     #[serde(default = "Config::default_log_format")]
     pub log_format: String,
+    /// Interval between the updates of the on-call statuses and alerts, unless overridden.
     #[serde(default = "Config::default_polling_interval_secs")]
     pub polling_interval_secs: u64,
+    /// Interval between the updates of the teams, their members, services and deployments.
+    #[serde(default = "Config::default_metadata_polling_interval_secs")]
+    pub teams_polling_interval_secs: u64,
+    /// Interval between the updates of the schedules.
+    #[serde(default = "Config::default_metadata_polling_interval_secs")]
+    pub schedules_polling_interval_secs: u64,
+    /// Interval between the updates of the on-call statuses and forwarding rules.
+    pub on_call_polling_interval_secs: Option<u64>,
+    /// Interval between the updates of the alert metrics.
+    pub alerts_polling_interval_secs: Option<u64>,
+    /// Maximum deviation of the polling intervals, as a fraction of the interval.
+    #[serde(default = "Config::default_polling_jitter")]
+    pub polling_jitter: f64,
+    #[serde(default)]
+    pub collection_mode: CollectionMode,
+    /// Number of polling intervals after which a data domain that failed to update
+    /// is reported as not ready.
+    #[serde(default = "Config::default_readiness_max_intervals")]
+    pub readiness_max_intervals: u32,
+    /// Bearer token required to force an update with `POST /refresh`.
+    /// The endpoint is disabled if not set.
+    pub refresh_token: Option<String>,
+    /// Whether to export the services owned by each team, the number of alerts
+    /// tagged with the name of each service and the number of open incidents impacting it.
+    #[serde(default)]
+    pub export_services: bool,
+    /// Whether to export the number of deployments for each service.
+    #[serde(default)]
+    pub export_deployments: bool,
+    /// Name of the Opsgenie heartbeat to ping after each successful update.
+    pub heartbeat_name: Option<String>,
+    /// Whether to create an Opsgenie alert when the updates keep failing.
+    /// The alert is closed once all the data domains are updated successfully again.
+    #[serde(default)]
+    pub alert_on_failure: bool,
+    /// Number of consecutive failed updates of a data domain after which the alert is created.
+    #[serde(default = "Config::default_failure_alert_threshold")]
+    pub failure_alert_threshold: u32,
+    /// Opsgenie search query to restrict the alerts taken into account.
+    pub alert_filter: Option<AlertFilter>,
+    /// Maximum number of concurrent requests to the alert API.
+    #[serde(default = "Config::default_api_concurrency")]
+    pub alert_api_concurrency: usize,
+    /// Maximum number of requests per minute to the alert API.
+    #[serde(default = "Config::default_api_requests_per_minute")]
+    pub alert_api_requests_per_minute: u32,
+    /// Maximum number of concurrent requests to the other APIs (teams, schedules, etc.).
+    #[serde(default = "Config::default_api_concurrency")]
+    pub configuration_api_concurrency: usize,
+    /// Maximum number of requests per minute to the other APIs (teams, schedules, etc.).
+    #[serde(default = "Config::default_api_requests_per_minute")]
+    pub configuration_api_requests_per_minute: u32,
 }
 
 impl Config {
     pub fn load(dotenv_path: &str) -> anyhow::Result<Self> {
         // Check if file exists. If not, load values from env.
-        let config = if std::path::Path::new(dotenv_path).exists() {
+        let config: Self = if std::path::Path::new(dotenv_path).exists() {
             let file = std::fs::File::open(dotenv_path).context("Can't open config file")?;
             // Create iterator over `KEY=VAL` pairs.
             // Somewhat ugly but whatever.
@@ -38,9 +128,47 @@ impl Config {
         } else {
             envy::from_env().context("Failed to load values from env")?
         };
+        config.validate()?;
         Ok(config)
     }
 
+    fn validate(&self) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            DataDomain::ALL
+                .into_iter()
+                .all(|domain| !self.polling_interval(domain).is_zero()),
+            "Polling intervals must be positive"
+        );
+        anyhow::ensure!(
+            (0.0..1.0).contains(&self.polling_jitter),
+            "POLLING_JITTER must be at least 0 and less than 1"
+        );
+        anyhow::ensure!(
+            self.failure_alert_threshold > 0,
+            "FAILURE_ALERT_THRESHOLD must be positive"
+        );
+        anyhow::ensure!(
+            self.readiness_max_intervals > 0,
+            "READINESS_MAX_INTERVALS must be positive"
+        );
+        anyhow::ensure!(
+            self.refresh_token
+                .as_ref()
+                .is_none_or(|token| !token.is_empty()),
+            "REFRESH_TOKEN must not be empty"
+        );
+        anyhow::ensure!(
+            self.alert_api_concurrency > 0 && self.configuration_api_concurrency > 0,
+            "API concurrency must be positive"
+        );
+        anyhow::ensure!(
+            self.alert_api_requests_per_minute > 0
+                && self.configuration_api_requests_per_minute > 0,
+            "API requests per minute must be positive"
+        );
+        Ok(())
+    }
+
     fn default_log_format() -> String {
         "plain".into()
     }
@@ -53,7 +181,55 @@ impl Config {
         60 * 5
     }
 
-    pub fn polling_interval(&self) -> Duration {
-        Duration::from_secs(self.polling_interval_secs)
+    fn default_metadata_polling_interval_secs() -> u64 {
+        60 * 60
+    }
+
+    fn default_polling_jitter() -> f64 {
+        0.1
+    }
+
+    fn default_readiness_max_intervals() -> u32 {
+        3
+    }
+
+    fn default_failure_alert_threshold() -> u32 {
+        3
+    }
+
+    fn default_api_concurrency() -> usize {
+        4
+    }
+
+    fn default_api_requests_per_minute() -> u32 {
+        300
+    }
+
+    pub fn polling_interval(&self, domain: DataDomain) -> Duration {
+        let secs = match domain {
+            DataDomain::Teams => self.teams_polling_interval_secs,
+            DataDomain::Schedules => self.schedules_polling_interval_secs,
+            DataDomain::OnCall => self
+                .on_call_polling_interval_secs
+                .unwrap_or(self.polling_interval_secs),
+            DataDomain::Alerts => self
+                .alerts_polling_interval_secs
+                .unwrap_or(self.polling_interval_secs),
+        };
+        Duration::from_secs(secs)
+    }
+
+    pub fn alert_api_limits(&self) -> DomainLimits {
+        DomainLimits {
+            concurrency: self.alert_api_concurrency,
+            requests_per_minute: self.alert_api_requests_per_minute,
+        }
+    }
+
+    pub fn configuration_api_limits(&self) -> DomainLimits {
+        DomainLimits {
+            concurrency: self.configuration_api_concurrency,
+            requests_per_minute: self.configuration_api_requests_per_minute,
+        }
     }
 }
@@ -0,0 +1,115 @@
+//! Readiness of the exporter, based on the freshness of each data domain.
+
+use std::{collections::BTreeMap, time::Duration};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// Outcome of the updates of a data domain so far.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct DomainHealth {
+    /// Time of the last update that succeeded completely.
+    pub last_success: Option<DateTime<Utc>>,
+    /// Whether the last update failed.
+    pub failing: bool,
+}
+
+impl DomainHealth {
+    /// The domain is ready if its last successful update is not older than `max_age`.
+    pub fn readiness(&self, max_age: Duration, now: DateTime<Utc>) -> DomainReadiness {
+        let age = self
+            .last_success
+            .map(|last_success| (now - last_success).to_std().unwrap_or_default());
+        DomainReadiness {
+            ready: age.is_some_and(|age| age <= max_age),
+            last_success: self.last_success,
+            age_secs: age.map(|age| age.as_secs()),
+            max_age_secs: max_age.as_secs(),
+            failing: self.failing,
+        }
+    }
+}
+
+/// Freshness of a data domain, as reported by `/readyz`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct DomainReadiness {
+    pub ready: bool,
+    pub last_success: Option<DateTime<Utc>>,
+    /// Seconds since the last successful update.
+    pub age_secs: Option<u64>,
+    pub max_age_secs: u64,
+    /// Whether the last update failed. Metrics keep their last known values meanwhile.
+    pub failing: bool,
+}
+
+/// Readiness of the exporter, as reported by `/readyz`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct Readiness {
+    /// Whether all the domains are ready.
+    pub ready: bool,
+    pub domains: BTreeMap<&'static str, DomainReadiness>,
+}
+
+impl Readiness {
+    pub fn new(domains: BTreeMap<&'static str, DomainReadiness>) -> Self {
+        Self {
+            ready: domains.values().all(|domain| domain.ready),
+            domains,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn domain_readiness() {
+        let now = Utc::now();
+        let max_age = Duration::from_secs(900);
+        let never_updated = DomainHealth::default().readiness(max_age, now);
+        assert!(!never_updated.ready);
+        assert_eq!(never_updated.age_secs, None);
+
+        let health = DomainHealth {
+            last_success: Some(now - chrono::Duration::minutes(10)),
+            failing: true,
+        };
+        let readiness = health.readiness(max_age, now);
+        assert!(readiness.ready);
+        assert_eq!(readiness.age_secs, Some(600));
+        assert!(readiness.failing);
+        assert!(!health.readiness(Duration::from_secs(300), now).ready);
+
+        let readiness = Readiness::new([("teams", readiness)].into());
+        assert!(readiness.ready);
+        let readiness = Readiness::new([("teams", never_updated)].into());
+        assert!(!readiness.ready);
+    }
+
+    #[test]
+    fn readiness_is_serialized() {
+        let now = "2024-07-19T17:00:00Z".parse().unwrap();
+        let health = DomainHealth {
+            last_success: Some(now),
+            failing: false,
+        };
+        let readiness =
+            Readiness::new([("alerts", health.readiness(Duration::from_secs(900), now))].into());
+        assert_eq!(
+            serde_json::to_value(&readiness).unwrap(),
+            serde_json::json!({
+                "ready": true,
+                "domains": {
+                    "alerts": {
+                        "ready": true,
+                        "last_success": "2024-07-19T17:00:00Z",
+                        "age_secs": 0,
+                        "max_age_secs": 900,
+                        "failing": false,
+                    },
+                },
+            })
+        );
+    }
+}
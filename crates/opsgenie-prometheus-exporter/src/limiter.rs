@@ -0,0 +1,226 @@
+//! Limits on the requests sent to each Opsgenie API domain.
+//!
+//! Opsgenie limits the number of requests per minute separately for each domain,
+//! e.g. for alerts and for the configuration (teams, schedules, etc.).
+//! [Corresponding API page](https://docs.opsgenie.com/docs/api-rate-limiting)
+
+use std::{sync::Arc, sync::Mutex, time::Duration};
+
+use opsgenie_client::transport::{BoxFuture, Transport};
+use tokio::{sync::Semaphore, time::Instant};
+
+use crate::metrics::EXPORTER_METRICS;
+
+/// Opsgenie API domain with its own rate limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ApiDomain {
+    Alert,
+    /// Teams, schedules, forwarding rules and the other endpoints.
+    Configuration,
+}
+
+impl ApiDomain {
+    /// Determines the domain from the request URL, e.g. `https://api.opsgenie.com/v2/alerts`.
+    fn of(url: &url::Url) -> Self {
+        let mut segments = url.path_segments().into_iter().flatten();
+        // Base URL may have a path prefix, so the resource is found after the API version.
+        let resource = segments
+            .by_ref()
+            .find(|segment| matches!(*segment, "v1" | "v2"))
+            .and_then(|_| segments.next());
+        match resource {
+            Some("alerts") => Self::Alert,
+            _ => Self::Configuration,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Alert => "alert",
+            Self::Configuration => "configuration",
+        }
+    }
+}
+
+/// Limits on the requests to an API domain.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct DomainLimits {
+    /// Maximum number of requests in flight.
+    pub concurrency: usize,
+    /// Maximum number of requests started per minute.
+    pub requests_per_minute: u32,
+}
+
+#[derive(Debug)]
+struct Limiter {
+    domain: ApiDomain,
+    semaphore: Semaphore,
+    /// Minimum interval between the starts of the requests.
+    interval: Duration,
+    /// Earliest time the next request may start.
+    next_start: Mutex<Instant>,
+}
+
+impl Limiter {
+    fn new(domain: ApiDomain, limits: DomainLimits) -> Self {
+        Self {
+            domain,
+            semaphore: Semaphore::new(limits.concurrency),
+            interval: Duration::from_secs(60) / limits.requests_per_minute,
+            next_start: Mutex::new(Instant::now()),
+        }
+    }
+
+    async fn execute(
+        &self,
+        inner: &dyn Transport,
+        request: reqwest::Request,
+    ) -> opsgenie_client::Result<reqwest::Response> {
+        let queued = Instant::now();
+        let _permit = self
+            .semaphore
+            .acquire()
+            .await
+            .expect("Semaphore is never closed");
+        let start = {
+            let mut next_start = self.next_start.lock().unwrap();
+            let start = (*next_start).max(Instant::now());
+            *next_start = start + self.interval;
+            start
+        };
+        tokio::time::sleep_until(start).await;
+        EXPORTER_METRICS.api_limiter_wait[&self.domain.as_str()].observe(queued.elapsed());
+        inner.execute(request).await
+    }
+}
+
+/// Transport enforcing the limits of each API domain on the requests sent with the inner one.
+#[derive(Debug)]
+pub(crate) struct LimitedTransport {
+    inner: Arc<dyn Transport>,
+    alert: Limiter,
+    configuration: Limiter,
+}
+
+impl LimitedTransport {
+    pub fn new(
+        inner: Arc<dyn Transport>,
+        alert: DomainLimits,
+        configuration: DomainLimits,
+    ) -> Self {
+        Self {
+            inner,
+            alert: Limiter::new(ApiDomain::Alert, alert),
+            configuration: Limiter::new(ApiDomain::Configuration, configuration),
+        }
+    }
+}
+
+impl Transport for LimitedTransport {
+    fn execute(
+        &self,
+        request: reqwest::Request,
+    ) -> BoxFuture<'_, opsgenie_client::Result<reqwest::Response>> {
+        let limiter = match ApiDomain::of(request.url()) {
+            ApiDomain::Alert => &self.alert,
+            ApiDomain::Configuration => &self.configuration,
+        };
+        Box::pin(limiter.execute(self.inner.as_ref(), request))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use futures_util::future::join_all;
+
+    use super::*;
+
+    /// Responds after a delay, keeping track of the requests in flight.
+    #[derive(Debug, Default)]
+    struct SlowTransport {
+        in_flight: AtomicUsize,
+        max_in_flight: AtomicUsize,
+        starts: Mutex<Vec<Instant>>,
+    }
+
+    impl Transport for SlowTransport {
+        fn execute(
+            &self,
+            _request: reqwest::Request,
+        ) -> BoxFuture<'_, opsgenie_client::Result<reqwest::Response>> {
+            Box::pin(async move {
+                self.starts.lock().unwrap().push(Instant::now());
+                let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                self.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_secs(3)).await;
+                self.in_flight.fetch_sub(1, Ordering::SeqCst);
+                Ok(http::Response::new("").into())
+            })
+        }
+    }
+
+    fn request(url: &str) -> reqwest::Request {
+        reqwest::Request::new(reqwest::Method::GET, url.parse().unwrap())
+    }
+
+    #[test]
+    fn domains() {
+        let domain = |url: &str| ApiDomain::of(request(url).url());
+        assert_eq!(
+            domain("https://api.opsgenie.com/v2/alerts/count"),
+            ApiDomain::Alert
+        );
+        assert_eq!(
+            domain("https://proxy.example.com/alerts/v2/alerts"),
+            ApiDomain::Alert
+        );
+        assert_eq!(
+            domain("https://api.opsgenie.com/v2/teams"),
+            ApiDomain::Configuration
+        );
+        assert_eq!(
+            domain("https://proxy.example.com/alerts/v2/schedules/1/on-calls"),
+            ApiDomain::Configuration
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn requests_are_limited() {
+        let inner = Arc::new(SlowTransport::default());
+        let limits = DomainLimits {
+            concurrency: 2,
+            requests_per_minute: 60,
+        };
+        let unlimited = DomainLimits {
+            concurrency: 100,
+            requests_per_minute: 60_000,
+        };
+        let transport = LimitedTransport::new(inner.clone(), limits, unlimited);
+        let started = Instant::now();
+        let requests =
+            (0..4).map(|_| transport.execute(request("https://api.opsgenie.com/v2/alerts")));
+        for response in join_all(requests).await {
+            response.unwrap();
+        }
+        assert_eq!(inner.max_in_flight.load(Ordering::SeqCst), 2);
+        // Requests are started at most once per second, and only when fewer than 2 are in flight.
+        let starts: Vec<_> = inner
+            .starts
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|start| (*start - started).as_secs())
+            .collect();
+        assert_eq!(starts, [0, 1, 3, 4]);
+
+        // Requests to other domains are not affected.
+        inner.starts.lock().unwrap().clear();
+        let started = Instant::now();
+        let requests =
+            (0..4).map(|_| transport.execute(request("https://api.opsgenie.com/v2/teams")));
+        join_all(requests).await;
+        assert!(started.elapsed() < Duration::from_secs(4));
+    }
+}
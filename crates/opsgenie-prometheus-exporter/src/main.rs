@@ -1,11 +1,23 @@
+use std::sync::Arc;
+
+use anyhow::Context as _;
 use clap::Parser;
-use config::Config;
+use config::{CollectionMode, Config};
+use metrics::MetricsSnapshot;
+use server::ServerState;
+use shutdown::Tasks;
+use tokio::net::TcpListener;
 use tracing_subscriber::{layer::SubscriberExt as _, util::SubscriberInitExt as _, EnvFilter};
 use updater::OpsgenieUpdater;
-use vise_exporter::MetricsExporter;
 
 mod config;
+mod health;
+mod limiter;
 mod metrics;
+mod server;
+mod shutdown;
+#[cfg(test)]
+mod test_utils;
 mod updater;
 
 fn init_tracing(json: bool) {
@@ -43,31 +55,45 @@ async fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
 
     let config = Config::load(cli.env_file.as_deref().unwrap_or(".env"))?;
-    init_tracing(config.log_format.to_ascii_lowercase() == "json");
+    init_tracing(config.log_format.eq_ignore_ascii_case("json"));
 
     tracing::info!("Starting up");
-    let polling_interval = config.polling_interval();
-    let updater = OpsgenieUpdater::new(
-        config.opsgenie_base_url,
-        config.opsgenie_api_key,
-        polling_interval,
-    );
-    let updater_task = tokio::spawn(updater.run());
+    let updater = OpsgenieUpdater::new(&config)?;
+    updater.check_account().await.with_context(|| {
+        format!(
+            "Unable to access the Opsgenie account. Check that OPSGENIE_API_KEY is valid and \
+             OPSGENIE_BASE_URL ({}) matches the region of your account \
+             (https://api.opsgenie.com for US, https://api.eu.opsgenie.com for EU)",
+            config.opsgenie_base_url
+        )
+    })?;
+    MetricsSnapshot::export(updater.snapshot());
+    let updater = Arc::new(updater);
 
-    let exporter = MetricsExporter::default();
-    let bind_address = format!("0.0.0.0:{}", config.prometheus_port)
-        .parse()
-        .unwrap();
-    let prometheus_task = tokio::spawn(exporter.start(bind_address));
+    let mut tasks = Tasks::new();
+    let router = server::router(ServerState::new(updater.clone(), &config));
+    let listener = TcpListener::bind(("0.0.0.0", config.prometheus_port))
+        .await
+        .with_context(|| format!("Failed to listen on port {}", config.prometheus_port))?;
+    tracing::info!("Serving metrics on {}", listener.local_addr()?);
+    // In-flight requests are completed before the server stops.
+    let shutdown = tasks.shutdown_requested();
+    tasks.spawn("Metrics server", async move {
+        axum::serve(listener, router)
+            .with_graceful_shutdown(shutdown)
+            .await?;
+        Ok(())
+    });
 
-    tokio::select! {
-        _ = updater_task => {
-            tracing::error!("Updater task failed");
-        }
-        _ = prometheus_task => {
-            tracing::error!("Prometheus task failed");
-        }
-    };
+    // In the scrape mode, metrics are updated by the server when scraped.
+    if config.collection_mode == CollectionMode::Polling {
+        let shutdown = tasks.shutdown_requested();
+        tasks.spawn("Updater", async move {
+            // The domain updates in progress are completed before the updater stops.
+            updater.run(shutdown).await;
+            Ok(())
+        });
+    }
 
-    Ok(())
+    tasks.run(shutdown::shutdown_signal()).await
 }
@@ -1,8 +1,13 @@
-use std::time::Duration;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
-use vise::{Buckets, Gauge, Histogram, LabeledFamily, Metrics};
+use opsgenie_client::metrics::{MetricsHook, RequestInfo};
+use vise::{Buckets, Counter, Gauge, Histogram, LabeledFamily, Metrics, Unit};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u64)]
 pub(crate) enum OnCallStatus {
     OnCall = 1,
@@ -15,6 +20,44 @@ const WEEK: f64 = 60.0 * 60.0 * 24.0 * 7.0;
 #[derive(Debug, Metrics)]
 #[metrics(prefix = "opsgenie")]
 pub(crate) struct OpsgenieMetrics {
+    /// Time from the creation of alerts until they were acknowledged (MTTA).
+    /// Observed once, when the alerts are closed.
+    #[metrics(
+        buckets = Buckets::exponential(MINUTE..=WEEK, 4.0),
+        unit = Unit::Seconds,
+        labels = ["team", "priority", "acknowledged_by"]
+    )]
+    pub alert_time_to_ack: LabeledFamily<(String, String, String), Histogram<Duration>, 3>,
+    /// Time from the creation of alerts until they were closed (MTTR).
+    /// `acknowledged_by` is empty for alerts closed without being acknowledged.
+    #[metrics(
+        buckets = Buckets::exponential(MINUTE..=WEEK, 4.0),
+        unit = Unit::Seconds,
+        labels = ["team", "priority", "acknowledged_by"]
+    )]
+    pub alert_time_to_close: LabeledFamily<(String, String, String), Histogram<Duration>, 3>,
+    /// Information about the Opsgenie account. Value is always `1`.
+    #[metrics(labels = ["name", "plan"])]
+    pub account_info: LabeledFamily<(String, String), Gauge<u64>, 2>,
+    /// Number of deployments observed for each service, by deployment state.
+    #[metrics(labels = ["service", "state"])]
+    pub deployments: LabeledFamily<(String, &'static str), Counter, 2>,
+}
+
+#[vise::register]
+pub(crate) static METRICS: vise::Global<OpsgenieMetrics> = vise::Global::new();
+
+/// Metrics whose series disappear when the corresponding Opsgenie entities are removed.
+///
+/// Entries can't be removed from metric families, so these metrics are built from
+/// the [`MetricsSnapshot`] on each scrape.
+#[derive(Debug, Metrics)]
+#[metrics(prefix = "opsgenie")]
+pub(crate) struct SnapshotMetrics {
+    /// Forwarding rules between users.
+    /// Value is `1` when the rule is active, and `0` otherwise.
+    #[metrics(labels = ["from_user", "to_user"])]
+    pub forwarding_active: LabeledFamily<(String, String), Gauge<u64>, 2>,
     /// Will export all team members on-call status.
     /// Value is `1` when the person is on-call, and `0` otherwise.
     #[metrics(labels = ["team", "schedule", "on_call"])]
@@ -22,10 +65,295 @@ pub(crate) struct OpsgenieMetrics {
     /// Number of alerts for each team.
     #[metrics(labels = ["team", "status", "priority"])]
     pub alerts: LabeledFamily<(String, &'static str, String), Gauge<u64>, 3>,
-    /// Alert live duration in seconds.
-    #[metrics(buckets = Buckets::exponential(MINUTE..=WEEK, 4.0), labels = ["team", "priority"])]
-    pub alert_duration: LabeledFamily<(String, String), Histogram<Duration>, 2>,
+    /// Age of the oldest open alert for each team.
+    #[metrics(unit = Unit::Seconds, labels = ["team", "priority"])]
+    pub oldest_open_alert_age: LabeledFamily<(String, String), Gauge<Duration>, 2>,
+    /// Number of open alerts for each team that are not older than `le` seconds.
+    #[metrics(labels = ["team", "priority", "le"])]
+    pub open_alerts_by_age: LabeledFamily<(String, String, String), Gauge<u64>, 3>,
+    /// Services owned by each team. Value is always `1`.
+    #[metrics(labels = ["team", "service"])]
+    pub service_info: LabeledFamily<(String, String), Gauge<u64>, 2>,
+    /// Number of alerts tagged with the name of each service, by the team owning the service.
+    #[metrics(labels = ["team", "service", "status"])]
+    pub service_alerts: LabeledFamily<(String, String, &'static str), Gauge<u64>, 3>,
+    /// Number of open incidents impacting each service, by the team owning the service.
+    #[metrics(labels = ["team", "service"])]
+    pub service_open_incidents: LabeledFamily<(String, String), Gauge<u64>, 2>,
 }
 
 #[vise::register]
-pub(crate) static METRICS: vise::Global<OpsgenieMetrics> = vise::Global::new();
+static SNAPSHOT_METRICS: vise::Collector<SnapshotMetrics> = vise::Collector::new();
+
+/// Upper bounds of the buckets of the open alerts by age, in seconds.
+const AGE_BUCKETS: [u64; 6] = [300, 3_600, 14_400, 86_400, 259_200, 604_800];
+
+/// Current values of the [`SnapshotMetrics`].
+#[derive(Debug, Default)]
+pub(crate) struct MetricsSnapshot {
+    /// Whether any forwarding rule between the users is active, by source and target username.
+    pub forwarding_active: HashMap<(String, String), bool>,
+    /// On-call status of the team members by team and schedule name.
+    pub on_call: HashMap<(String, String), HashMap<String, OnCallStatus>>,
+    /// Alerts by team.
+    pub alerts: HashMap<String, TeamAlerts>,
+    /// Services by ID.
+    pub services: HashMap<String, ServiceInfo>,
+    /// Alerts by service ID.
+    pub service_alerts: HashMap<String, ServiceAlerts>,
+    /// Number of open incidents by impacted service ID.
+    pub service_open_incidents: HashMap<String, u64>,
+}
+
+/// Labels of the metrics of a service.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ServiceInfo {
+    /// Name of the owner team.
+    pub team: String,
+    pub name: String,
+}
+
+/// Number of alerts of a service by status.
+pub(crate) type ServiceAlerts = HashMap<&'static str, u64>;
+
+#[derive(Debug, Default)]
+pub(crate) struct TeamAlerts {
+    /// Number of alerts by status and priority.
+    pub counts: HashMap<(&'static str, String), u64>,
+    /// Ages of the open alerts by priority. Priorities without open alerts are omitted.
+    pub open_ages: HashMap<String, AlertAges>,
+}
+
+/// Age distribution of open alerts.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct AlertAges {
+    pub oldest: Duration,
+    /// Number of alerts not older than each of [`AGE_BUCKETS`], followed by the total number.
+    pub buckets: [u64; AGE_BUCKETS.len() + 1],
+}
+
+impl AlertAges {
+    /// Returns `None` if there are no ages.
+    pub fn new(ages: impl IntoIterator<Item = Duration>) -> Option<Self> {
+        let mut oldest = None;
+        let mut buckets = [0; AGE_BUCKETS.len() + 1];
+        for age in ages {
+            oldest = oldest.max(Some(age));
+            let first_bucket = AGE_BUCKETS
+                .iter()
+                .position(|&bound| age.as_secs_f64() <= bound as f64)
+                .unwrap_or(AGE_BUCKETS.len());
+            for count in &mut buckets[first_bucket..] {
+                *count += 1;
+            }
+        }
+        Some(Self {
+            oldest: oldest?,
+            buckets,
+        })
+    }
+
+    /// Sets the total number of alerts, including the ones whose ages are unknown,
+    /// so that the last bucket matches the number of open alerts.
+    pub fn with_total(mut self, total: u64) -> Self {
+        let last = self.buckets.last_mut().unwrap();
+        *last = (*last).max(total);
+        self
+    }
+}
+
+impl MetricsSnapshot {
+    fn to_metrics(&self) -> SnapshotMetrics {
+        let metrics = SnapshotMetrics::default();
+        for (users, active) in &self.forwarding_active {
+            metrics.forwarding_active[users].set(*active as u64);
+        }
+        for ((team, schedule), statuses) in &self.on_call {
+            for (user, status) in statuses {
+                metrics.on_call[&(team.clone(), schedule.clone(), user.clone())]
+                    .set(*status as u64);
+            }
+        }
+        for (team, alerts) in &self.alerts {
+            for ((status, priority), count) in &alerts.counts {
+                metrics.alerts[&(team.clone(), *status, priority.clone())].set(*count);
+            }
+            for (priority, ages) in &alerts.open_ages {
+                metrics.oldest_open_alert_age[&(team.clone(), priority.clone())].set(ages.oldest);
+                let bounds = AGE_BUCKETS
+                    .iter()
+                    .map(ToString::to_string)
+                    .chain(["+Inf".to_string()]);
+                for (bound, count) in bounds.zip(ages.buckets) {
+                    metrics.open_alerts_by_age[&(team.clone(), priority.clone(), bound)].set(count);
+                }
+            }
+        }
+        for service in self.services.values() {
+            metrics.service_info[&(service.team.clone(), service.name.clone())].set(1);
+        }
+        for (id, alerts) in &self.service_alerts {
+            let Some(service) = self.services.get(id) else {
+                continue;
+            };
+            for (status, count) in alerts {
+                let labels = (service.team.clone(), service.name.clone(), *status);
+                metrics.service_alerts[&labels].set(*count);
+            }
+        }
+        for (id, count) in &self.service_open_incidents {
+            if let Some(service) = self.services.get(id) {
+                let labels = (service.team.clone(), service.name.clone());
+                metrics.service_open_incidents[&labels].set(*count);
+            }
+        }
+        metrics
+    }
+
+    /// Exports the snapshot on each scrape.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a snapshot is already exported.
+    pub fn export(snapshot: Arc<Mutex<Self>>) {
+        SNAPSHOT_METRICS
+            .before_scrape(move || snapshot.lock().unwrap().to_metrics())
+            .expect("Metrics snapshot is already exported");
+    }
+}
+
+/// Metrics about the exporter itself, to check that the exported data is fresh.
+#[derive(Debug, Metrics)]
+#[metrics(prefix = "opsgenie_exporter")]
+pub(crate) struct ExporterMetrics {
+    /// Duration of the updates of each data domain.
+    #[metrics(
+        buckets = Buckets::exponential(1.0..=1024.0, 2.0),
+        unit = Unit::Seconds,
+        labels = ["domain"]
+    )]
+    pub step_duration: LabeledFamily<&'static str, Histogram<Duration>>,
+    /// Unix timestamp of the last update of each data domain that succeeded completely.
+    #[metrics(unit = Unit::Seconds, labels = ["domain"])]
+    pub last_success_timestamp: LabeledFamily<&'static str, Gauge<u64>>,
+    /// Number of failed updates, by the part of the update that failed and the kind of error.
+    /// Metrics of the failed part keep their last known values.
+    #[metrics(labels = ["scope", "kind"])]
+    pub update_errors: LabeledFamily<(&'static str, &'static str), Counter, 2>,
+    /// Number of requests sent to the Opsgenie API, by endpoint and response status.
+    /// Status is `no_response` when the request failed before a response was received.
+    #[metrics(labels = ["method", "endpoint", "status"])]
+    pub api_requests: LabeledFamily<(String, String, String), Counter, 3>,
+    /// Latency of the Opsgenie API requests, excluding the time waiting for the request limits.
+    #[metrics(buckets = Buckets::LATENCIES, unit = Unit::Seconds, labels = ["method", "endpoint"])]
+    pub api_latency: LabeledFamily<(String, String), Histogram<Duration>, 2>,
+    /// Time the requests waited for the configured request limits of each API domain
+    /// (`alert` or `configuration`) before being sent.
+    #[metrics(
+        buckets = Buckets::exponential(0.001..=65.536, 4.0),
+        unit = Unit::Seconds,
+        labels = ["domain"]
+    )]
+    pub api_limiter_wait: LabeledFamily<&'static str, Histogram<Duration>>,
+    /// Number of requests rejected by the Opsgenie API rate limiting.
+    #[metrics(labels = ["endpoint"])]
+    pub api_rate_limited: LabeledFamily<String, Counter>,
+}
+
+#[vise::register]
+pub(crate) static EXPORTER_METRICS: vise::Global<ExporterMetrics> = vise::Global::new();
+
+/// Exports the metrics of the requests sent by the Opsgenie client.
+#[derive(Debug)]
+pub(crate) struct ApiMetricsHook;
+
+impl MetricsHook for ApiMetricsHook {
+    fn on_request(&self, request: &RequestInfo) {
+        let method = request.method.to_string();
+        let status = match request.status {
+            Some(status) => status.as_u16().to_string(),
+            None => "no_response".to_string(),
+        };
+        EXPORTER_METRICS.api_requests[&(method.clone(), request.endpoint.clone(), status)].inc();
+        EXPORTER_METRICS.api_latency[&(method, request.endpoint.clone())].observe(request.latency);
+        if request.rate_limited {
+            EXPORTER_METRICS.api_rate_limited[&request.endpoint].inc();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use vise::{Format, Registry};
+
+    use super::*;
+
+    #[test]
+    fn snapshot_is_encoded() {
+        let mut snapshot = MetricsSnapshot::default();
+        snapshot.on_call.insert(
+            ("ops".into(), "ops_schedule".into()),
+            HashMap::from([
+                ("neo".into(), OnCallStatus::OnCall),
+                ("trinity".into(), OnCallStatus::NotOnCall),
+            ]),
+        );
+        let ages = [60, 600, 7_200].map(Duration::from_secs);
+        snapshot.alerts.insert(
+            "ops".into(),
+            TeamAlerts {
+                counts: HashMap::from([(("open", "P1".into()), 3)]),
+                open_ages: HashMap::from([("P1".into(), AlertAges::new(ages).unwrap())]),
+            },
+        );
+        let service = ServiceInfo {
+            team: "ops".into(),
+            name: "checkout".into(),
+        };
+        snapshot.services.insert("checkout_id".into(), service);
+        snapshot
+            .service_alerts
+            .insert("checkout_id".into(), HashMap::from([("open", 2)]));
+        // Alerts of unknown services are not exported.
+        snapshot
+            .service_alerts
+            .insert("deleted_id".into(), HashMap::from([("open", 1)]));
+        snapshot
+            .service_open_incidents
+            .insert("checkout_id".into(), 1);
+
+        let metrics = snapshot.to_metrics();
+        let mut registry = Registry::empty();
+        registry.register_metrics(&metrics);
+        let mut buffer = String::new();
+        registry.encode(&mut buffer, Format::OpenMetrics).unwrap();
+        let entries = [
+            r#"opsgenie_on_call{team="ops",schedule="ops_schedule",on_call="neo"} 1"#,
+            r#"opsgenie_on_call{team="ops",schedule="ops_schedule",on_call="trinity"} 0"#,
+            r#"opsgenie_alerts{team="ops",status="open",priority="P1"} 3"#,
+            r#"opsgenie_oldest_open_alert_age_seconds{team="ops",priority="P1"} 7200"#,
+            r#"opsgenie_open_alerts_by_age{team="ops",priority="P1",le="300"} 1"#,
+            r#"opsgenie_open_alerts_by_age{team="ops",priority="P1",le="3600"} 2"#,
+            r#"opsgenie_open_alerts_by_age{team="ops",priority="P1",le="14400"} 3"#,
+            r#"opsgenie_open_alerts_by_age{team="ops",priority="P1",le="+Inf"} 3"#,
+            r#"opsgenie_service_info{team="ops",service="checkout"} 1"#,
+            r#"opsgenie_service_alerts{team="ops",service="checkout",status="open"} 2"#,
+            r#"opsgenie_service_open_incidents{team="ops",service="checkout"} 1"#,
+        ];
+        for entry in entries {
+            assert!(buffer.contains(entry), "{buffer}");
+        }
+        assert_eq!(buffer.matches("opsgenie_service_alerts{").count(), 1);
+    }
+
+    #[test]
+    fn alert_ages() {
+        assert_eq!(AlertAges::new([]), None);
+        let ages = [0, 300, 301, 86_400, 1_000_000].map(Duration::from_secs);
+        let ages = AlertAges::new(ages).unwrap();
+        assert_eq!(ages.oldest, Duration::from_secs(1_000_000));
+        assert_eq!(ages.buckets, [2, 3, 3, 4, 4, 4, 5]);
+        let ages = ages.with_total(8);
+        assert_eq!(ages.buckets, [2, 3, 3, 4, 4, 4, 8]);
+    }
+}
@@ -0,0 +1,252 @@
+//! HTTP server exporting the metrics to Prometheus and controlling the updater.
+
+use std::sync::Arc;
+
+use axum::{
+    extract::State,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use vise::{Format, Registry};
+
+use crate::{
+    config::{CollectionMode, Config},
+    health::Readiness,
+    updater::OpsgenieUpdater,
+};
+
+#[derive(Debug, Clone)]
+pub(crate) struct ServerState {
+    updater: Arc<OpsgenieUpdater>,
+    registry: Arc<Registry>,
+    collection_mode: CollectionMode,
+    refresh_token: Option<Arc<str>>,
+}
+
+impl ServerState {
+    /// Exports all the metrics registered in the app.
+    pub fn new(updater: Arc<OpsgenieUpdater>, config: &Config) -> Self {
+        Self {
+            updater,
+            registry: Arc::new(vise::MetricsCollection::default().collect()),
+            collection_mode: config.collection_mode,
+            refresh_token: config.refresh_token.as_deref().map(Arc::from),
+        }
+    }
+}
+
+/// Creates the router serving the metrics and the probes, and `POST /refresh`
+/// if a refresh token is configured.
+pub(crate) fn router(state: ServerState) -> Router {
+    let mut router = Router::new()
+        .route("/metrics", get(metrics))
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz));
+    if state.refresh_token.is_some() {
+        router = router.route("/refresh", post(refresh));
+    }
+    router.with_state(state)
+}
+
+async fn metrics(State(state): State<ServerState>) -> Response {
+    if state.collection_mode == CollectionMode::Scrape {
+        // The update is spawned so that it's not cancelled if the scrape times out.
+        // Failures are logged by the updater, and the last known values are exported meanwhile.
+        let updater = state.updater.clone();
+        tokio::spawn(async move { updater.refresh(false).await })
+            .await
+            .ok();
+    }
+    let mut buffer = String::new();
+    state
+        .registry
+        .encode(&mut buffer, Format::OpenMetricsForPrometheus)
+        .expect("Writing to a string never fails");
+    (
+        [(header::CONTENT_TYPE, Format::OPEN_METRICS_CONTENT_TYPE)],
+        buffer,
+    )
+        .into_response()
+}
+
+/// Liveness probe: the process is able to serve requests.
+async fn healthz() -> &'static str {
+    "OK"
+}
+
+/// Readiness probe: all the data domains were updated recently.
+async fn readyz(State(state): State<ServerState>) -> (StatusCode, Json<Readiness>) {
+    let readiness = state.updater.readiness();
+    let status = if readiness.ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(readiness))
+}
+
+/// Forces an update of all the metrics, and responds once it's finished.
+async fn refresh(State(state): State<ServerState>, headers: HeaderMap) -> (StatusCode, String) {
+    let expected = state
+        .refresh_token
+        .as_deref()
+        .expect("Route is only served with a token");
+    let token = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    if !token.is_some_and(|token| constant_time_eq(token.as_bytes(), expected.as_bytes())) {
+        return (StatusCode::UNAUTHORIZED, "Invalid refresh token".into());
+    }
+
+    tracing::info!("Refresh requested");
+    let updater = state.updater.clone();
+    let result = tokio::spawn(async move { updater.refresh(true).await }).await;
+    match result.unwrap_or_else(|err| Err(err.into())) {
+        Ok(()) => (StatusCode::OK, "Metrics updated".into()),
+        Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, format!("{err:#}")),
+    }
+}
+
+/// Compares the tokens in a time independent of their common prefix.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use opsgenie_mock::{MockServer, MockState};
+    use tokio::net::TcpListener;
+
+    use super::*;
+    use crate::{metrics::OnCallStatus, test_utils::config};
+
+    /// Serves the exporter for the mock server, and returns its base URL.
+    async fn serve(server: &MockServer, vars: &[(&str, &str)]) -> (String, Arc<OpsgenieUpdater>) {
+        let config = config(server, vars);
+        let updater = Arc::new(OpsgenieUpdater::new(&config).unwrap());
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let router = router(ServerState::new(updater.clone(), &config));
+        tokio::spawn(async move { axum::serve(listener, router).await });
+        (format!("http://{address}"), updater)
+    }
+
+    fn mock_state(on_call: &str) -> MockState {
+        let mut state = MockState::new();
+        let team = state.add_team("server_team", &["neo", "trinity"]);
+        let schedule = state.add_schedule("server_schedule", &team);
+        state.set_on_call(&schedule, &[on_call]);
+        state
+    }
+
+    /// Returns whether the user is on call according to the last update.
+    fn is_on_call(updater: &OpsgenieUpdater, user: &str) -> bool {
+        let snapshot = updater.snapshot();
+        let snapshot = snapshot.lock().unwrap();
+        let schedule = ("server_team".to_owned(), "server_schedule".to_owned());
+        snapshot
+            .on_call
+            .get(&schedule)
+            .is_some_and(|statuses| statuses.get(user) == Some(&OnCallStatus::OnCall))
+    }
+
+    // Time is paused to skip the delays between the rate limited requests.
+    #[tokio::test(start_paused = true)]
+    async fn refresh_requires_token() {
+        let server = MockServer::start(mock_state("neo")).await;
+        let (url, updater) = serve(&server, &[("REFRESH_TOKEN", "secret")]).await;
+        let client = reqwest::Client::new();
+        let refresh = |token: Option<&str>| {
+            let request = client.post(format!("{url}/refresh"));
+            match token {
+                Some(token) => request.bearer_auth(token),
+                None => request,
+            }
+            .send()
+        };
+
+        let response = refresh(None).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = refresh(Some("guess")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        // Metrics are only updated in the background in the polling mode.
+        let metrics = client.get(format!("{url}/metrics")).send().await.unwrap();
+        assert_eq!(
+            metrics.headers()[header::CONTENT_TYPE],
+            Format::OPEN_METRICS_CONTENT_TYPE
+        );
+        assert!(!is_on_call(&updater, "neo"));
+
+        let response = refresh(Some("secret")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(is_on_call(&updater, "neo"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn probes() {
+        let server = MockServer::start(mock_state("neo")).await;
+        let (url, updater) = serve(&server, &[]).await;
+        let client = reqwest::Client::new();
+        let response = client.get(format!("{url}/healthz")).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let readyz = || async {
+            let response = client.get(format!("{url}/readyz")).send().await.unwrap();
+            let status = response.status();
+            (status, response.json::<serde_json::Value>().await.unwrap())
+        };
+        let (status, readiness) = readyz().await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(readiness["ready"], false);
+        assert_eq!(
+            readiness["domains"]["alerts"]["last_success"],
+            serde_json::Value::Null
+        );
+
+        updater.refresh(true).await.unwrap();
+        let (status, readiness) = readyz().await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(readiness["ready"], true);
+        assert_eq!(readiness["domains"]["teams"]["max_age_secs"], 3 * 3600);
+        assert_eq!(readiness["domains"]["on_call"]["failing"], false);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn refresh_is_disabled_without_token() {
+        let server = MockServer::start(mock_state("neo")).await;
+        let (url, _) = serve(&server, &[]).await;
+        let response = reqwest::Client::new()
+            .post(format!("{url}/refresh"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn scrapes_update_stale_metrics() {
+        let server = MockServer::start(mock_state("trinity")).await;
+        let (url, updater) = serve(&server, &[("COLLECTION_MODE", "scrape")]).await;
+        let scrape = || async {
+            let response = reqwest::get(format!("{url}/metrics")).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        };
+
+        // Concurrent scrapes wait for the same update.
+        tokio::join!(scrape(), scrape());
+        assert!(is_on_call(&updater, "trinity"));
+
+        // Metrics are cached until they are older than the polling interval.
+        {
+            let mut state = server.state();
+            let schedule = state.schedules[0].id.clone();
+            state.set_on_call(&schedule, &["neo"]);
+        }
+        scrape().await;
+        assert!(is_on_call(&updater, "trinity"));
+        assert!(!is_on_call(&updater, "neo"));
+    }
+}
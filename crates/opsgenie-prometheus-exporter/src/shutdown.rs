@@ -0,0 +1,168 @@
+//! Graceful shutdown of the exporter tasks.
+
+use std::{future::Future, time::Duration};
+
+use anyhow::Context as _;
+use tokio::{
+    signal::unix::{signal, SignalKind},
+    sync::watch,
+    task::JoinSet,
+};
+
+/// Time given to the tasks to stop after the shutdown is requested, before they are aborted.
+/// Shorter than the default termination grace period of Kubernetes.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Resolves once SIGTERM or SIGINT is received.
+pub(crate) async fn shutdown_signal() -> anyhow::Result<()> {
+    let mut terminate = signal(SignalKind::terminate())?;
+    tokio::select! {
+        _ = terminate.recv() => tracing::info!("Received SIGTERM"),
+        result = tokio::signal::ctrl_c() => {
+            result?;
+            tracing::info!("Received SIGINT");
+        }
+    }
+    Ok(())
+}
+
+/// Tasks running until the shutdown, e.g. the metrics server and the updater.
+#[derive(Debug)]
+pub(crate) struct Tasks {
+    tasks: JoinSet<anyhow::Result<()>>,
+    shutdown: watch::Sender<bool>,
+}
+
+impl Tasks {
+    pub fn new() -> Self {
+        Self {
+            tasks: JoinSet::new(),
+            shutdown: watch::Sender::new(false),
+        }
+    }
+
+    /// Returns a future resolving once the shutdown is requested. Tasks should stop then.
+    pub fn shutdown_requested(&self) -> impl Future<Output = ()> + Send + 'static {
+        let mut shutdown = self.shutdown.subscribe();
+        async move {
+            // The sender lives until all the tasks stop.
+            shutdown.wait_for(|requested| *requested).await.ok();
+        }
+    }
+
+    /// Spawns a task. Tasks are expected to run until the shutdown is requested.
+    pub fn spawn(
+        &mut self,
+        name: &'static str,
+        task: impl Future<Output = anyhow::Result<()>> + Send + 'static,
+    ) {
+        let shutdown = self.shutdown.subscribe();
+        self.tasks.spawn(async move {
+            task.await.with_context(|| format!("{name} failed"))?;
+            anyhow::ensure!(*shutdown.borrow(), "{name} stopped unexpectedly");
+            tracing::info!("{name} stopped");
+            Ok(())
+        });
+    }
+
+    /// Runs the tasks until `signal` resolves or one of the tasks stops, then requests
+    /// the others to stop and waits for them. Fails if any of the tasks failed.
+    pub async fn run(
+        mut self,
+        signal: impl Future<Output = anyhow::Result<()>>,
+    ) -> anyhow::Result<()> {
+        let mut result = tokio::select! {
+            result = signal => result.context("Failed to listen for shutdown signals"),
+            Some(result) = self.tasks.join_next() => flatten(result),
+        };
+        if let Err(err) = &result {
+            tracing::error!("{:#}", err);
+        }
+
+        tracing::info!("Shutting down");
+        self.shutdown.send_replace(true);
+        let stopped = tokio::time::timeout(SHUTDOWN_TIMEOUT, async {
+            while let Some(task_result) = self.tasks.join_next().await {
+                if let Err(err) = flatten(task_result) {
+                    tracing::error!("{:#}", err);
+                    if result.is_ok() {
+                        result = Err(err);
+                    }
+                }
+            }
+        })
+        .await;
+        if stopped.is_err() {
+            // Remaining tasks are aborted when dropped.
+            tracing::warn!(
+                "{} tasks didn't stop within {:?}",
+                self.tasks.len(),
+                SHUTDOWN_TIMEOUT
+            );
+        }
+        result
+    }
+}
+
+fn flatten(result: Result<anyhow::Result<()>, tokio::task::JoinError>) -> anyhow::Result<()> {
+    result.context("Task panicked")?
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    };
+
+    use super::*;
+
+    /// Spawns a task that runs until the shutdown, and returns whether it stopped.
+    fn spawn_graceful(tasks: &mut Tasks) -> Arc<AtomicBool> {
+        let stopped = Arc::new(AtomicBool::new(false));
+        let shutdown = tasks.shutdown_requested();
+        let task_stopped = stopped.clone();
+        tasks.spawn("Graceful task", async move {
+            shutdown.await;
+            task_stopped.store(true, Ordering::SeqCst);
+            Ok(())
+        });
+        stopped
+    }
+
+    #[tokio::test]
+    async fn tasks_stop_on_signal() {
+        let mut tasks = Tasks::new();
+        let stopped = spawn_graceful(&mut tasks);
+        tasks.run(async { Ok(()) }).await.unwrap();
+        assert!(stopped.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn failed_task_stops_the_others() {
+        let mut tasks = Tasks::new();
+        let stopped = spawn_graceful(&mut tasks);
+        tasks.spawn("Broken task", async { anyhow::bail!("Port is in use") });
+        let err = tasks.run(std::future::pending()).await.unwrap_err();
+        assert_eq!(format!("{err:#}"), "Broken task failed: Port is in use");
+        assert!(stopped.load(Ordering::SeqCst));
+
+        let mut tasks = Tasks::new();
+        tasks.spawn("Short task", async { Ok(()) });
+        let err = tasks.run(std::future::pending()).await.unwrap_err();
+        assert_eq!(err.to_string(), "Short task stopped unexpectedly");
+
+        let mut tasks = Tasks::new();
+        tasks.spawn("Panicking task", async { panic!("Unexpected state") });
+        let err = tasks.run(std::future::pending()).await.unwrap_err();
+        assert_eq!(err.to_string(), "Task panicked");
+    }
+
+    // Time is paused to skip the shutdown timeout.
+    #[tokio::test(start_paused = true)]
+    async fn stuck_tasks_are_aborted() {
+        let mut tasks = Tasks::new();
+        tasks.spawn("Stuck task", std::future::pending());
+        tasks.run(async { Ok(()) }).await.unwrap();
+    }
+}
@@ -0,0 +1,17 @@
+use opsgenie_mock::MockServer;
+
+use crate::config::Config;
+
+/// Creates a configuration for the mock server, with the provided environment variables.
+pub fn config(server: &MockServer, vars: &[(&str, &str)]) -> Config {
+    let url = server.url();
+    let defaults = [
+        ("OPSGENIE_BASE_URL", url.as_str()),
+        ("OPSGENIE_API_KEY", opsgenie_mock::API_KEY),
+    ];
+    let vars = defaults
+        .iter()
+        .chain(vars)
+        .map(|(key, value)| (key.to_string(), value.to_string()));
+    envy::from_iter(vars).unwrap()
+}
@@ -1,175 +1,1796 @@
-use crate::metrics::{OnCallStatus, METRICS};
+use crate::{
+    config::{AlertFilter, Config},
+    health::{DomainHealth, Readiness},
+    limiter::LimitedTransport,
+    metrics::{
+        AlertAges, ApiMetricsHook, MetricsSnapshot, OnCallStatus, ServiceAlerts, ServiceInfo,
+        TeamAlerts, EXPORTER_METRICS, METRICS,
+    },
+};
+use anyhow::Context as _;
+use chrono::{DateTime, Utc};
+use futures_util::future::{join_all, FutureExt as _, Shared};
 use opsgenie_client::{
-    query_builder::{Query, ToFilter as _},
-    OpsgenieClient,
+    api::{
+        alert::{
+            request::{CloseAlert, CreateAlert},
+            response::{Alert, Report},
+            IdentifierType,
+        },
+        deployment::response::{Deployment, DeploymentState},
+        schedule::response::Schedule,
+        service::response::Service,
+    },
+    metrics::MetricsTransport,
+    pagination::{Order, Pagination},
+    query_builder::{AlertStatus, Priority, Query, ToFilter},
+    ClientError, OpsgenieClient,
 };
 use std::{
     collections::{HashMap, HashSet},
-    time::{Duration, SystemTime},
+    future::Future,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
 };
-use url::Url;
+use tokio::time::Instant;
+
+/// Alias of the alert created when the updater fails.
+/// Opsgenie deduplicates open alerts with the same alias.
+const FAILURE_ALERT_ALIAS: &str = "opsgenie-prometheus-exporter-failure";
+/// Delay before retrying after the first failed step.
+const MIN_RETRY_DELAY: Duration = Duration::from_secs(15);
+/// Maximum number of alerts fetched for a query; should be representative enough.
+const MAX_ALERTS_TO_FETCH: usize = 1_000;
+
+/// Part of the Opsgenie data updated on its own schedule.
+///
+/// Teams and schedules change rarely, so they are cached and shared with the on-call statuses
+/// and alerts, which are updated more often.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum DataDomain {
+    /// Teams with their members, and optionally the services and deployments.
+    Teams,
+    Schedules,
+    /// On-call statuses and forwarding rules.
+    OnCall,
+    Alerts,
+}
+
+impl DataDomain {
+    /// Domains in the order of their dependencies.
+    pub const ALL: [Self; 4] = [Self::Teams, Self::Schedules, Self::OnCall, Self::Alerts];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Teams => "teams",
+            Self::Schedules => "schedules",
+            Self::OnCall => "on_call",
+            Self::Alerts => "alerts",
+        }
+    }
+}
+
+/// Team and schedule metadata shared by the data domains.
+#[derive(Debug, Default)]
+struct Metadata {
+    /// Members of each team. Teams that failed to update keep their last known members.
+    team_members: HashMap<String, HashSet<String>>,
+    /// Schedules of each team, or `None` until the schedules are fetched.
+    team_schedules: Option<HashMap<String, Vec<Schedule>>>,
+    /// Services by ID, if the services are exported.
+    services: HashMap<String, ServiceInfo>,
+}
+
+/// Update schedule of a data domain.
+#[derive(Debug)]
+struct DomainState {
+    consecutive_failures: u32,
+    /// Time when the next update is due.
+    next_update: Instant,
+}
+
+impl Default for DomainState {
+    fn default() -> Self {
+        Self {
+            consecutive_failures: 0,
+            next_update: Instant::now(),
+        }
+    }
+}
 
 #[derive(Debug)]
 pub(crate) struct OpsgenieUpdater {
     client: OpsgenieClient,
-    polling_interval: Duration,
+    polling_intervals: HashMap<DataDomain, Duration>,
+    /// Maximum deviation of the polling intervals, as a fraction of the interval.
+    polling_jitter: f64,
+    export_services: bool,
+    export_deployments: bool,
+    /// Name of the Opsgenie heartbeat to ping after each successful update.
+    heartbeat_name: Option<String>,
+    alert_on_failure: bool,
+    /// Number of consecutive failures of a domain after which the failure is reported.
+    failure_alert_threshold: u32,
+    /// Whether the failure alert may be open, including an alert created before a restart.
+    failure_alert_open: AtomicBool,
+    /// Opsgenie search query to restrict the alerts taken into account.
+    alert_filter: Option<AlertFilter>,
+    metadata: Mutex<Metadata>,
+    /// Locked during the updates, so that each domain is updated by one task at a time.
+    domains: HashMap<DataDomain, tokio::sync::Mutex<DomainState>>,
+    /// Outcome of the updates of each domain so far.
+    health: Mutex<HashMap<DataDomain, DomainHealth>>,
+    /// Number of polling intervals after which a domain that failed to update is not ready.
+    readiness_max_intervals: u32,
+    /// Last observed state of each recent deployment, to count every state change only once,
+    /// or `None` until the deployments are fetched.
+    deployment_states: Mutex<Option<HashMap<String, DeploymentState>>>,
+    /// Last known active forwarding rules, used when the rules can't be fetched.
+    forwards: Mutex<HashMap<String, String>>,
+    /// Current on-call statuses and alert counts, without the removed teams, schedules and members.
+    snapshot: Arc<Mutex<MetricsSnapshot>>,
+    /// Closed alerts of each team observed so far.
+    closed_alerts: Mutex<HashMap<String, ClosedAlertsWatermark>>,
 }
 
 impl OpsgenieUpdater {
-    pub fn new(url: Url, api_key: String, polling_interval: Duration) -> Self {
-        let client = OpsgenieClient::new(url, api_key);
-        Self {
+    pub fn new(config: &Config) -> anyhow::Result<Self> {
+        let client = OpsgenieClient::builder(config.opsgenie_api_key.clone())
+            .base_url(config.opsgenie_base_url.clone())
+            .build()
+            .context("Invalid Opsgenie client configuration")?;
+        // Requests are measured once they are let through by the limits.
+        let transport =
+            MetricsTransport::new(client.transport(), client.base_url(), ApiMetricsHook);
+        let transport = LimitedTransport::new(
+            Arc::new(transport),
+            config.alert_api_limits(),
+            config.configuration_api_limits(),
+        );
+        let client = client.with_transport(transport);
+        Ok(Self {
             client,
-            polling_interval,
+            polling_intervals: DataDomain::ALL
+                .into_iter()
+                .map(|domain| (domain, config.polling_interval(domain)))
+                .collect(),
+            polling_jitter: config.polling_jitter,
+            export_services: config.export_services,
+            export_deployments: config.export_deployments,
+            heartbeat_name: config.heartbeat_name.clone(),
+            alert_on_failure: config.alert_on_failure,
+            failure_alert_threshold: config.failure_alert_threshold,
+            failure_alert_open: AtomicBool::new(true),
+            alert_filter: config.alert_filter.clone(),
+            metadata: Mutex::default(),
+            domains: DataDomain::ALL
+                .into_iter()
+                .map(|domain| (domain, tokio::sync::Mutex::default()))
+                .collect(),
+            health: Mutex::default(),
+            readiness_max_intervals: config.readiness_max_intervals,
+            deployment_states: Mutex::default(),
+            forwards: Mutex::default(),
+            snapshot: Arc::default(),
+            closed_alerts: Mutex::default(),
+        })
+    }
+
+    /// Returns the metrics that are replaced on each update, to be exported.
+    pub fn snapshot(&self) -> Arc<Mutex<MetricsSnapshot>> {
+        self.snapshot.clone()
+    }
+
+    /// Returns whether each domain was updated successfully within the last
+    /// [`Config::readiness_max_intervals`] of its polling intervals.
+    pub fn readiness(&self) -> Readiness {
+        let health = self.health.lock().unwrap();
+        let now = Utc::now();
+        let domains = DataDomain::ALL.into_iter().map(|domain| {
+            let max_age = self.polling_intervals[&domain] * self.readiness_max_intervals;
+            let health = health.get(&domain).copied().unwrap_or_default();
+            (domain.as_str(), health.readiness(max_age, now))
+        });
+        Readiness::new(domains.collect())
+    }
+
+    /// Fetches the account information to check that the API is reachable
+    /// with the provided configuration.
+    pub async fn check_account(&self) -> anyhow::Result<()> {
+        let account = self.client.account().get().await?;
+        tracing::info!(
+            "Connected to Opsgenie account {} ({} plan, {} users)",
+            account.data.name,
+            account.data.plan.name,
+            account.data.user_count
+        );
+        METRICS.account_info[&(account.data.name, account.data.plan.name)].set(1);
+        Ok(())
+    }
+
+    /// Updates the metrics until `shutdown` resolves.
+    ///
+    /// Each data domain is updated on its own schedule. Failed updates are retried with
+    /// an exponential backoff. Meanwhile, the metrics keep their last known values.
+    /// The domain updates in progress are completed before returning, so that the metrics
+    /// of a domain are never left partially updated.
+    pub async fn run(&self, shutdown: impl Future<Output = ()>) {
+        let shutdown = shutdown.shared();
+        // Domains are first updated in order, so that the on-call statuses and alerts
+        // are exported as soon as the teams and schedules are known.
+        for domain in DataDomain::ALL {
+            if shutdown.clone().now_or_never().is_some() {
+                return;
+            }
+            // Errors are logged by the updates.
+            self.update_if_due(domain, false).await.ok();
         }
+        join_all(DataDomain::ALL.map(|domain| self.schedule(domain, shutdown.clone()))).await;
     }
 
-    pub async fn run(self) -> anyhow::Result<()> {
+    /// Updates the domain whenever it is due until `shutdown` resolves.
+    async fn schedule<F: Future<Output = ()>>(&self, domain: DataDomain, shutdown: Shared<F>) {
         loop {
-            self.step().await?;
-            tokio::time::sleep(self.polling_interval).await;
+            let next_update = self.domains[&domain].lock().await.next_update;
+            tokio::select! {
+                biased;
+                () = shutdown.clone() => return,
+                () = tokio::time::sleep_until(next_update) => {}
+            }
+            self.update_if_due(domain, false).await.ok();
+        }
+    }
+
+    /// Updates the domains in order, either all of them or only the ones that are due,
+    /// and returns the first error.
+    pub async fn refresh(&self, force: bool) -> anyhow::Result<()> {
+        let mut result = Ok(());
+        for domain in DataDomain::ALL {
+            let domain_result = self.update_if_due(domain, force).await;
+            if result.is_ok() {
+                result = domain_result;
+            }
+        }
+        result
+    }
+
+    /// Updates the domain if it is due or `force` is set. Waits for the concurrent update
+    /// of the domain to finish first, so that the domain is not updated twice in a row.
+    async fn update_if_due(&self, domain: DataDomain, force: bool) -> anyhow::Result<()> {
+        let mut state = self.domains[&domain].lock().await;
+        if force || state.next_update <= Instant::now() {
+            self.update(domain, &mut state).await
+        } else {
+            Ok(())
         }
     }
 
-    async fn step(&self) -> anyhow::Result<()> {
-        // Get all teams
-        let team_descriptors = self.client.team().list_teams().await?;
+    /// Updates a single domain and schedules its next update.
+    async fn update(&self, domain: DataDomain, state: &mut DomainState) -> anyhow::Result<()> {
+        let started = Instant::now();
+        let mut errors = StepErrors::default();
+        self.update_domain(domain, &mut errors).await;
+        let result = errors
+            .into_result()
+            .with_context(|| format!("Failed to update {}", domain.as_str()));
+        EXPORTER_METRICS.step_duration[&domain.as_str()].observe(started.elapsed());
+        let polling_interval = self.polling_intervals[&domain];
+        let delay = match &result {
+            Ok(()) => {
+                state.consecutive_failures = 0;
+                EXPORTER_METRICS.last_success_timestamp[&domain.as_str()]
+                    .set(Utc::now().timestamp() as u64);
+                let all_succeeded = {
+                    let mut health = self.health.lock().unwrap();
+                    *health.entry(domain).or_default() = DomainHealth {
+                        last_success: Some(Utc::now()),
+                        failing: false,
+                    };
+                    health.values().all(|health| !health.failing)
+                };
+                if all_succeeded {
+                    if let Some(heartbeat_name) = &self.heartbeat_name {
+                        if let Err(err) = self.client.heartbeat().ping(heartbeat_name).await {
+                            tracing::warn!("Failed to ping heartbeat {}: {}", heartbeat_name, err);
+                        }
+                    }
+                    if self.alert_on_failure {
+                        self.resolve_failure().await;
+                    }
+                }
+                polling_interval
+            }
+            Err(err) => {
+                state.consecutive_failures += 1;
+                self.health
+                    .lock()
+                    .unwrap()
+                    .entry(domain)
+                    .or_default()
+                    .failing = true;
+                tracing::error!(
+                    "Failed to update metrics ({} consecutive failures): {:#}",
+                    state.consecutive_failures,
+                    err
+                );
+                if self.alert_on_failure
+                    && state.consecutive_failures >= self.failure_alert_threshold
+                {
+                    self.report_failure(err).await;
+                }
+                retry_delay(state.consecutive_failures, polling_interval)
+            }
+        };
+        state.next_update = Instant::now() + jittered(delay, self.polling_jitter);
+        result
+    }
+
+    /// Updates the domain.
+    ///
+    /// Teams, schedules and the other parts of the update are isolated from each other:
+    /// if one of them fails, the others are still updated, and the error is collected.
+    async fn update_domain(&self, domain: DataDomain, errors: &mut StepErrors) {
+        match domain {
+            DataDomain::Teams => self.update_teams(errors).await,
+            DataDomain::Schedules => self.update_schedules(errors).await,
+            DataDomain::OnCall => self.update_on_call(errors).await,
+            DataDomain::Alerts => self.update_alerts(errors).await,
+        }
+    }
+
+    /// Restricts the query to the alerts matching the configured filter.
+    fn alert_query(&self, query: impl ToFilter) -> Box<dyn ToFilter> {
+        match &self.alert_filter {
+            Some(filter) => Box::new(query.and(filter.clone())),
+            None => Box::new(query),
+        }
+    }
+
+    /// Creates an Opsgenie alert about the updater failure.
+    async fn report_failure(&self, err: &anyhow::Error) {
+        let mut alert = CreateAlert::new("Opsgenie Prometheus exporter failed to update metrics");
+        alert.alias = Some(FAILURE_ALERT_ALIAS.to_string());
+        alert.description = Some(format!("{err:#}"));
+        alert.source = Some("opsgenie-prometheus-exporter".to_string());
+        match self.client.alert().create(&alert).await {
+            Ok(_) => self.failure_alert_open.store(true, Ordering::Relaxed),
+            Err(err) => tracing::error!("Failed to create an alert about the failure: {}", err),
+        }
+    }
+
+    /// Closes the alert about the updater failure, if it may be open.
+    async fn resolve_failure(&self) {
+        if !self.failure_alert_open.load(Ordering::Relaxed) {
+            return;
+        }
+        let request = CloseAlert {
+            source: Some("opsgenie-prometheus-exporter".to_string()),
+            note: Some("All the metrics are updated successfully again".to_string()),
+            ..CloseAlert::default()
+        };
+        let result = self
+            .client
+            .alert()
+            .close(FAILURE_ALERT_ALIAS, IdentifierType::Alias, &request)
+            .await;
+        match result {
+            Ok(_) => self.failure_alert_open.store(false, Ordering::Relaxed),
+            Err(err) => tracing::warn!("Failed to close the alert about the failure: {}", err),
+        }
+    }
+
+    /// Updates the team members, and the services and deployments if enabled.
+    async fn update_teams(&self, errors: &mut StepErrors) {
+        let team_descriptors = self.client.team().list_teams().await;
+        let Some(team_descriptors) =
+            errors.check("teams", team_descriptors.context("Failed to list teams"))
+        else {
+            return;
+        };
+        let previous_members = std::mem::take(&mut self.metadata.lock().unwrap().team_members);
         let mut team_members = HashMap::new();
-        for team_desc in team_descriptors.data {
-            let team = self.client.team().get(team_desc.id).await?;
-            team_members.insert(team_desc.name.clone(), HashSet::new());
+        let mut team_names = HashMap::new();
+        let teams = join_all(
+            team_descriptors
+                .data
+                .into_iter()
+                .map(|team_desc| async move {
+                    let team = self.client.team().get(team_desc.id.clone()).await;
+                    (team_desc, team)
+                }),
+        )
+        .await;
+        for (team_desc, team) in teams {
+            team_names.insert(team_desc.id.clone(), team_desc.name.clone());
+            let Some(team) = errors.check(
+                "team",
+                team.with_context(|| format!("Failed to get team {}", team_desc.name)),
+            ) else {
+                if let Some(members) = previous_members.get(&team_desc.name) {
+                    team_members.insert(team_desc.name, members.clone());
+                }
+                continue;
+            };
+            let members = team_members
+                .entry(team_desc.name.clone())
+                .or_insert_with(HashSet::new);
 
-            let Some(members) = team.data.members else {
+            let Some(team_members) = team.data.members else {
                 tracing::warn!("Team {} has no members", team_desc.name);
                 continue;
             };
-            for member in members {
+            for member in team_members {
                 let Some(username) = member.user.username else {
                     tracing::warn!("Member has no username: {:?}", member);
                     continue;
                 };
-
-                team_members
-                    .get_mut(&team_desc.name)
-                    .unwrap()
-                    .insert(username);
+                members.insert(username);
             }
         }
 
-        for (team, members) in team_members.clone() {
+        for (team, members) in &team_members {
             tracing::info!("Team: {}", team);
             for member in members {
                 tracing::info!("  - {}", member);
             }
         }
+        self.metadata.lock().unwrap().team_members = team_members;
+
+        if self.export_services || self.export_deployments {
+            let services = self.list_services().await;
+            if let Some(services) =
+                errors.check("services", services.context("Failed to list services"))
+            {
+                if self.export_services {
+                    let services = self.update_services(&team_names, &services);
+                    self.metadata.lock().unwrap().services = services.clone();
+                    // Series of the services that no longer exist are removed.
+                    self.snapshot.lock().unwrap().services = services;
+                }
+                if self.export_deployments {
+                    let result = self.update_deployments(&services).await;
+                    errors.check(
+                        "deployments",
+                        result.context("Failed to update deployments"),
+                    );
+                }
+            }
+        }
+    }
 
-        // Get all schedules
-        let schedules = self.client.schedule().list_schedules().await?;
+    /// Updates the schedules of each team.
+    async fn update_schedules(&self, errors: &mut StepErrors) {
+        let schedules = self.client.schedule().list_schedules().await;
+        let Some(schedules) =
+            errors.check("schedules", schedules.context("Failed to list schedules"))
+        else {
+            return;
+        };
 
-        // Sort them by team.
         let mut team_schedules = HashMap::new();
-        // Also store all team members for each schedule to report who is on call.
-        let mut not_on_call = HashMap::new();
         for schedule in schedules.data {
-            let team = schedule.owner_team.name.clone();
             team_schedules
-                .entry(team.clone())
+                .entry(schedule.owner_team.name.clone())
                 .or_insert_with(Vec::new)
-                .push(schedule.clone());
-            tracing::info!(
-                "Adding members for schedule {}; team {}",
-                schedule.name,
-                team
+                .push(schedule);
+        }
+        self.metadata.lock().unwrap().team_schedules = Some(team_schedules);
+    }
+
+    /// Returns the cached schedules of each team.
+    fn team_schedules(&self) -> anyhow::Result<HashMap<String, Vec<Schedule>>> {
+        let metadata = self.metadata.lock().unwrap();
+        metadata
+            .team_schedules
+            .clone()
+            .context("Schedules were not fetched yet")
+    }
+
+    /// Updates the forwarding rules and the on-call statuses of the cached schedules.
+    async fn update_on_call(&self, errors: &mut StepErrors) {
+        let Some(team_schedules) = errors.check("schedules", self.team_schedules()) else {
+            return;
+        };
+        let team_members = self.metadata.lock().unwrap().team_members.clone();
+
+        // People that are on-call may forward their notifications to someone else.
+        let forwards = self.update_forwarding_rules().await;
+        let forwards = match errors.check(
+            "forwarding_rules",
+            forwards.context("Failed to update forwarding rules"),
+        ) {
+            Some(forwards) => {
+                *self.forwards.lock().unwrap() = forwards.clone();
+                forwards
+            }
+            None => self.forwards.lock().unwrap().clone(),
+        };
+
+        // Requests are sent concurrently; their rate is limited by the client transport.
+        // Members of teams that couldn't be fetched are only reported when on call.
+        let no_members = HashSet::new();
+        let schedule_updates = team_schedules.iter().flat_map(|(team, schedules)| {
+            let members = team_members.get(team).unwrap_or(&no_members);
+            let forwards = &forwards;
+            schedules.iter().map(move |schedule| async move {
+                let result = self.update_schedule(schedule, members, forwards).await;
+                (team, schedule, result)
+            })
+        });
+        for (team, schedule, result) in join_all(schedule_updates).await {
+            let result =
+                result.with_context(|| format!("Failed to update schedule {}", schedule.name));
+            if let Some(statuses) = errors.check("schedule", result) {
+                let mut snapshot = self.snapshot.lock().unwrap();
+                snapshot
+                    .on_call
+                    .insert((team.clone(), schedule.name.clone()), statuses);
+            }
+        }
+
+        // Series of the schedules that no longer exist are removed.
+        // Series of the ones that failed to update keep their last known values.
+        let current_schedules: HashSet<_> = team_schedules
+            .iter()
+            .flat_map(|(team, schedules)| {
+                schedules
+                    .iter()
+                    .map(|schedule| (team.clone(), schedule.name.clone()))
+            })
+            .collect();
+        self.snapshot
+            .lock()
+            .unwrap()
+            .on_call
+            .retain(|schedule, _| current_schedules.contains(schedule));
+    }
+
+    /// Updates the alert metrics of the teams owning the cached schedules.
+    async fn update_alerts(&self, errors: &mut StepErrors) {
+        let Some(team_schedules) = errors.check("schedules", self.team_schedules()) else {
+            return;
+        };
+
+        // TODO: filter out teams that had an alert in the last week.
+        let alert_updates = team_schedules.keys().map(|team| async move {
+            let alerts = self.update_team_alerts(team).await;
+            let closed_alerts = self.observe_closed_alerts(team).await;
+            (team, alerts, closed_alerts)
+        });
+        for (team, alerts, closed_alerts) in join_all(alert_updates).await {
+            let alerts = alerts.with_context(|| format!("Failed to update alerts of team {team}"));
+            if let Some(alerts) = errors.check("alerts", alerts) {
+                self.snapshot
+                    .lock()
+                    .unwrap()
+                    .alerts
+                    .insert(team.clone(), alerts);
+            }
+            errors.check(
+                "closed_alerts",
+                closed_alerts
+                    .with_context(|| format!("Failed to observe closed alerts of team {team}")),
             );
-            not_on_call.insert(schedule.name, team_members[&team].clone());
-        }
-
-        for (team, schedules) in team_schedules.clone() {
-            tracing::info!("Team: {}", team.clone());
-            for schedule in schedules {
-                let on_call = self.client.on_call().whoisoncall(&schedule.id).await?;
-
-                tracing::info!("  - Schedule {}:", &schedule.name);
-                for recipient in on_call.data.on_call_recipients {
-                    tracing::info!("    - {}", recipient);
-                    METRICS.on_call[&(team.clone(), schedule.name.clone(), recipient.clone())]
-                        .set(OnCallStatus::OnCall as u64);
-                    not_on_call
-                        .entry(schedule.name.clone())
-                        .and_modify(|members| {
-                            members.remove(&recipient);
-                        });
-                }
-                for team_member in &not_on_call[&schedule.name] {
-                    METRICS.on_call[&(team.clone(), schedule.name.clone(), team_member.clone())]
-                        .set(OnCallStatus::NotOnCall as u64);
-                }
+        }
+
+        // Series of the teams that no longer exist are removed.
+        self.snapshot
+            .lock()
+            .unwrap()
+            .alerts
+            .retain(|team, _| team_schedules.contains_key(team));
+        self.closed_alerts
+            .lock()
+            .unwrap()
+            .retain(|team, _| team_schedules.contains_key(team));
+
+        let services = self.metadata.lock().unwrap().services.clone();
+        let service_updates = services.iter().map(|(id, service)| async move {
+            let alerts = self.update_service_alerts(&service.name).await;
+            (id, service, alerts)
+        });
+        for (id, service, alerts) in join_all(service_updates).await {
+            let alerts = alerts
+                .with_context(|| format!("Failed to update alerts of service {}", service.name));
+            if let Some(alerts) = errors.check("service_alerts", alerts) {
+                self.snapshot
+                    .lock()
+                    .unwrap()
+                    .service_alerts
+                    .insert(id.clone(), alerts);
             }
+        }
+        // Series of the services that no longer exist are removed.
+        self.snapshot
+            .lock()
+            .unwrap()
+            .service_alerts
+            .retain(|id, _| services.contains_key(id));
+
+        if self.export_services {
+            let incidents = self
+                .count_open_incidents(&services)
+                .await
+                .context("Failed to count open incidents");
+            if let Some(incidents) = errors.check("service_incidents", incidents) {
+                self.snapshot.lock().unwrap().service_open_incidents = incidents;
+            }
+        }
+    }
 
-            // TODO: Hack to avoid rate limiting.
-            tokio::time::sleep(Duration::from_secs(5)).await;
+    /// Returns the on-call status of the team members for the schedule.
+    async fn update_schedule(
+        &self,
+        schedule: &Schedule,
+        members: &HashSet<String>,
+        forwards: &HashMap<String, String>,
+    ) -> anyhow::Result<HashMap<String, OnCallStatus>> {
+        let on_call = self.client.on_call().whoisoncall(&schedule.id).await?;
 
-            // TODO: filter out teams that had an alert in the last week.
+        tracing::info!(
+            "Schedule {}: {:?} on call",
+            schedule.name,
+            on_call.data.on_call_recipients
+        );
+        let mut statuses: HashMap<_, _> = members
+            .iter()
+            .map(|member| (member.clone(), OnCallStatus::NotOnCall))
+            .collect();
+        // Recipients forwarding their notifications are replaced with the target of the rule.
+        for recipient in on_call.data.on_call_recipients {
+            if let Some(to_user) = forwards.get(&recipient) {
+                statuses.insert(to_user.clone(), OnCallStatus::OnCall);
+                statuses.entry(recipient).or_insert(OnCallStatus::NotOnCall);
+            } else {
+                statuses.insert(recipient, OnCallStatus::OnCall);
+            }
+        }
+        Ok(statuses)
+    }
 
-            for priority in (1..=5).map(|p| format!("P{p}")) {
-                let total = self
-                    .client
-                    .alert()
-                    .count(
-                        Query::new("team", team.clone())
-                            .and(Query::new("priority", priority.clone())),
-                    )
+    /// Returns the number of alerts of the team by status and priority,
+    /// and the ages of its open alerts.
+    async fn update_team_alerts(&self, team: &str) -> anyhow::Result<TeamAlerts> {
+        let now = Utc::now();
+        let priority_updates = Priority::ALL.into_iter().map(|priority| async move {
+            let total = self
+                .client
+                .alert()
+                .count(self.alert_query(
+                    Query::new("team", team.to_owned()).and(Query::priority(priority)),
+                ))
+                .await?;
+            tracing::info!(
+                "Team {} has {} alerts with priority {}",
+                team,
+                total.data.count,
+                priority
+            );
+
+            let open_query = || {
+                self.alert_query(
+                    Query::new("team", team.to_owned())
+                        .and(Query::priority(priority).and(Query::status(AlertStatus::Open))),
+                )
+            };
+            let open = self.client.alert().count(open_query()).await?;
+            tracing::info!(
+                "Team {} has {} open alerts with priority {}",
+                team,
+                open.data.count,
+                priority
+            );
+            let mut open_ages = None;
+            if open.data.count > 0 {
+                // The oldest alerts are fetched first, so that the oldest age is always exact.
+                // If the listing is truncated, the newest alerts are only counted in the last
+                // bucket.
+                let open_alerts = self
+                    .list_alerts(open_query, "createdAt", Some(MAX_ALERTS_TO_FETCH))
                     .await?;
-                METRICS.alerts[&(team.clone(), "total", priority.clone())].set(total.data.count);
-                tracing::info!(
-                    "Team {} has {} alerts with priority {}",
-                    team,
-                    total.data.count,
-                    priority
+                let ages = open_alerts
+                    .iter()
+                    .filter_map(|alert| (now - alert.created_at.to_utc()).to_std().ok());
+                open_ages = AlertAges::new(ages).map(|ages| ages.with_total(open.data.count));
+            }
+            anyhow::Ok((priority, total.data.count, open.data.count, open_ages))
+        });
+
+        let mut alerts = TeamAlerts::default();
+        for result in join_all(priority_updates).await {
+            let (priority, total, open, open_ages) = result?;
+            let priority = priority.to_string();
+            alerts.counts.insert(("total", priority.clone()), total);
+            alerts.counts.insert(("open", priority.clone()), open);
+            if let Some(open_ages) = open_ages {
+                alerts.open_ages.insert(priority, open_ages);
+            }
+        }
+        Ok(alerts)
+    }
+
+    /// Observes the time to acknowledge and close of the alerts of the team that were closed
+    /// since the previous call. Alerts closed before the first call are not observed.
+    async fn observe_closed_alerts(&self, team: &str) -> anyhow::Result<()> {
+        let watermark = self.closed_alerts.lock().unwrap().get(team).cloned();
+        let Some(mut watermark) = watermark else {
+            let watermark = ClosedAlertsWatermark::new(Utc::now());
+            self.closed_alerts
+                .lock()
+                .unwrap()
+                .insert(team.to_owned(), watermark);
+            return Ok(());
+        };
+
+        // Alerts are updated when closed, so the ones closed since the watermark are among these.
+        let query = || {
+            self.alert_query(
+                Query::new("team", team.to_owned())
+                    .and(Query::status(AlertStatus::Closed))
+                    .and(Query::greater_or_equal(
+                        "updatedAt",
+                        watermark.closed_at.timestamp_millis(),
+                    )),
+            )
+        };
+        // All of them are listed: the watermark moves past the listed alerts, so the ones that
+        // were left out would never be observed.
+        let alerts = self.list_alerts(query, "updatedAt", None).await?;
+        for (alert, report) in watermark.advance(&alerts) {
+            let acknowledged_by = report.acknowledged_by.clone().unwrap_or_default();
+            let labels = (team.to_owned(), alert.priority.clone(), acknowledged_by);
+            if let Some(ack_time) = report.ack_time {
+                METRICS.alert_time_to_ack[&labels].observe(Duration::from_millis(ack_time));
+            }
+            if let Some(close_time) = report.close_time {
+                METRICS.alert_time_to_close[&labels].observe(Duration::from_millis(close_time));
+            }
+        }
+        self.closed_alerts
+            .lock()
+            .unwrap()
+            .insert(team.to_owned(), watermark);
+        Ok(())
+    }
+
+    /// Lists the alerts matching the query, sorted in ascending order by the field,
+    /// up to `max_alerts` if set.
+    async fn list_alerts(
+        &self,
+        query: impl Fn() -> Box<dyn ToFilter>,
+        sort: &str,
+        max_alerts: Option<usize>,
+    ) -> anyhow::Result<Vec<Alert>> {
+        let mut pagination = Pagination::new()
+            .with_max_limit()
+            .with_sort(sort.to_string())
+            .with_order(Order::Asc);
+        let mut alerts = Vec::new();
+        loop {
+            let page = self.client.alert().list_page(query(), &pagination).await?;
+            let fetched = page.data.len();
+            alerts.extend(page.data);
+            if fetched < pagination.limit as usize {
+                break;
+            }
+            if max_alerts.is_some_and(|max_alerts| alerts.len() >= max_alerts) {
+                tracing::warn!(
+                    "Only the first {} alerts are taken into account for the query {}",
+                    alerts.len(),
+                    query().to_filter()
                 );
+                break;
+            }
+            pagination = pagination.next();
+        }
+        Ok(alerts)
+    }
 
-                let open = self
-                    .client
-                    .alert()
-                    .count(Query::new("team", team.clone()).and(
-                        Query::new("priority", priority.clone()).and(Query::new("status", "open")),
-                    ))
-                    .await?;
-                METRICS.alerts[&(team.clone(), "open", priority.clone())].set(open.data.count);
+    /// Exports forwarding rules.
+    /// Returns the mapping between users and the recipients of their notifications for active rules.
+    async fn update_forwarding_rules(&self) -> anyhow::Result<HashMap<String, String>> {
+        let rules = self.client.forwarding_rule().list().await?;
+        let now = Utc::now().fixed_offset();
+        let mut forwards = HashMap::new();
+        let mut forwarding_active = HashMap::new();
+        for rule in rules.data {
+            let (Some(from_user), Some(to_user)) = (
+                rule.from_user.username.clone(),
+                rule.to_user.username.clone(),
+            ) else {
+                tracing::warn!("Forwarding rule has no usernames: {:?}", rule);
+                continue;
+            };
+            let active = rule.is_active_at(now);
+            *forwarding_active
+                .entry((from_user.clone(), to_user.clone()))
+                .or_insert(false) |= active;
+            if active {
                 tracing::info!(
-                    "Team {} has {} open alerts with priority {}",
-                    team,
-                    open.data.count,
-                    priority
+                    "Notifications for {} are forwarded to {}",
+                    from_user,
+                    to_user
                 );
-                if open.data.count > 0 {
-                    // Get at most 100 alerts; should be representative enough.
-                    const MAX_ALERTS_TO_FETCH: u32 = 100;
-                    let alerts = self
-                        .client
-                        .alert()
-                        .list(
-                            Query::new("team", team.clone())
-                                .and(Query::new("priority", priority.clone()))
-                                .and(Query::new("status", "open")),
-                            Some(MAX_ALERTS_TO_FETCH),
-                        )
-                        .await?;
-                    for alert in alerts.data {
-                        let unix_timestamp = alert.created_at.timestamp();
-                        let alert_system_time =
-                            SystemTime::UNIX_EPOCH + Duration::from_secs(unix_timestamp as u64);
-                        let now = SystemTime::now();
-
-                        if let Ok(duration) = now.duration_since(alert_system_time) {
-                            METRICS.alert_duration[&(team.clone(), priority.clone())]
-                                .observe(duration);
-                        }
+                forwards.insert(from_user, to_user);
+            }
+        }
+        // Series of the deleted rules are removed.
+        self.snapshot.lock().unwrap().forwarding_active = forwarding_active;
+        Ok(forwards)
+    }
+
+    async fn list_services(&self) -> anyhow::Result<Vec<Service>> {
+        let mut pagination = Pagination::new().with_max_limit();
+        let mut services = Vec::new();
+        loop {
+            let page = self.client.service().list(&pagination).await?;
+            let fetched = page.data.len();
+            services.extend(page.data);
+            if fetched < pagination.limit as usize {
+                break;
+            }
+            pagination = pagination.next();
+        }
+        Ok(services)
+    }
+
+    /// Returns the services with known owner teams by ID.
+    /// `team_names` maps team IDs to their names.
+    fn update_services(
+        &self,
+        team_names: &HashMap<String, String>,
+        services: &[Service],
+    ) -> HashMap<String, ServiceInfo> {
+        let mut service_infos = HashMap::new();
+        for service in services {
+            let Some(team) = team_names.get(&service.team_id) else {
+                tracing::warn!("Service {} has unknown owner team", service.name);
+                continue;
+            };
+            tracing::info!("Team {} owns service {}", team, service.name);
+            let info = ServiceInfo {
+                team: team.clone(),
+                name: service.name.clone(),
+            };
+            service_infos.insert(service.id.clone(), info);
+        }
+        service_infos
+    }
+
+    /// Returns the number of alerts tagged with the service name by status.
+    async fn update_service_alerts(&self, service: &str) -> anyhow::Result<ServiceAlerts> {
+        let query = || Query::tag(service.to_owned());
+        let alert_api = self.client.alert();
+        let (total, open) = tokio::try_join!(
+            alert_api.count(self.alert_query(query())),
+            alert_api.count(self.alert_query(query().and(Query::status(AlertStatus::Open)))),
+        )?;
+        tracing::info!(
+            "Service {} has {} alerts, {} of them open",
+            service,
+            total.data.count,
+            open.data.count
+        );
+        Ok([("total", total.data.count), ("open", open.data.count)].into())
+    }
+
+    /// Returns the number of open incidents impacting each of the services by service ID.
+    async fn count_open_incidents(
+        &self,
+        services: &HashMap<String, ServiceInfo>,
+    ) -> anyhow::Result<HashMap<String, u64>> {
+        let mut counts: HashMap<_, _> = services.keys().map(|id| (id.clone(), 0)).collect();
+        let mut pagination = Pagination::new().with_max_limit();
+        loop {
+            let query = Query::new("status", "open");
+            let page = self.client.incident().list(query, &pagination).await?;
+            let fetched = page.data.len();
+            for incident in page.data {
+                for service_id in &incident.impacted_services {
+                    if let Some(count) = counts.get_mut(service_id) {
+                        *count += 1;
                     }
                 }
             }
+            if fetched < pagination.limit as usize {
+                break;
+            }
+            pagination = pagination.next();
         }
+        Ok(counts)
+    }
+
+    /// Counts the state changes of the recent deployments. The states fetched first are only
+    /// recorded, so that the deployments are not counted again after each restart.
+    async fn update_deployments(&self, services: &[Service]) -> anyhow::Result<()> {
+        let service_names: HashMap<_, _> = services
+            .iter()
+            .map(|service| (service.id.as_str(), service.name.as_str()))
+            .collect();
+        let deployments = self.list_recent_deployments().await?;
 
+        let mut states = self.deployment_states.lock().unwrap();
+        let mut recent_states = HashMap::with_capacity(deployments.len());
+        for deployment in deployments {
+            // Deployments created while paging shift the pages, so some are listed twice.
+            if recent_states.contains_key(&deployment.id) {
+                continue;
+            }
+            let changed = states
+                .as_ref()
+                .is_some_and(|states| states.get(&deployment.id) != Some(&deployment.state));
+            if changed {
+                tracing::info!(
+                    "Deployment {} is in state {}",
+                    deployment.message,
+                    deployment.state.as_str()
+                );
+                // Deployments without services are exported with an empty `service` label.
+                let services = if deployment.service_ids.is_empty() {
+                    vec![String::new()]
+                } else {
+                    deployment
+                        .service_ids
+                        .iter()
+                        .map(|id| service_names.get(id.as_str()).copied().unwrap_or(id))
+                        .map(str::to_owned)
+                        .collect()
+                };
+                for service in services {
+                    METRICS.deployments[&(service, deployment.state.as_str())].inc();
+                }
+            }
+            recent_states.insert(deployment.id, deployment.state);
+        }
+        // Deployments that are no longer among the recent ones won't be seen again.
+        *states = Some(recent_states);
         Ok(())
     }
+
+    /// Lists the deployments, newest first, down to the first page with a deployment seen in the
+    /// previous update. Only the first page is listed on the first update.
+    async fn list_recent_deployments(&self) -> anyhow::Result<Vec<Deployment>> {
+        let mut pagination = Pagination::new()
+            .with_max_limit()
+            .with_sort("createdAt".to_string())
+            .with_order(Order::Desc);
+        let mut deployments = Vec::new();
+        loop {
+            let page = self.client.deployment().list(&pagination).await?;
+            let fetched = page.data.len();
+            let reached_seen = match &*self.deployment_states.lock().unwrap() {
+                Some(states) => page
+                    .data
+                    .iter()
+                    .any(|deployment| states.contains_key(&deployment.id)),
+                None => true,
+            };
+            deployments.extend(page.data);
+            if reached_seen || fetched < pagination.limit as usize {
+                return Ok(deployments);
+            }
+            pagination = pagination.next();
+        }
+    }
+}
+
+/// Position up to which the closed alerts of a team were observed.
+///
+/// Timestamps of the alerts are used rather than the local time, so that each alert is observed
+/// exactly once, even if it's updated after being closed.
+#[derive(Debug, Clone)]
+struct ClosedAlertsWatermark {
+    /// Closing time of the last observed alerts.
+    closed_at: DateTime<Utc>,
+    /// Alerts closed exactly at `closed_at` that were already observed.
+    observed: HashSet<String>,
+}
+
+impl ClosedAlertsWatermark {
+    fn new(closed_at: DateTime<Utc>) -> Self {
+        Self {
+            closed_at,
+            observed: HashSet::new(),
+        }
+    }
+
+    /// Returns the alerts closed after the watermark with their reports,
+    /// and moves the watermark past them.
+    fn advance<'a>(&mut self, alerts: &'a [Alert]) -> Vec<(&'a Alert, &'a Report)> {
+        let previous = self.clone();
+        let mut closed = Vec::new();
+        for alert in alerts {
+            let Some(report) = &alert.report else {
+                continue;
+            };
+            let Some(close_time) = report.close_time else {
+                continue;
+            };
+            // Time to close is measured from the creation of the alert.
+            let closed_at =
+                alert.created_at.to_utc() + chrono::Duration::milliseconds(close_time as i64);
+            let observed = closed_at < previous.closed_at
+                || (closed_at == previous.closed_at && previous.observed.contains(&alert.id));
+            if observed {
+                continue;
+            }
+            if closed_at > self.closed_at {
+                self.closed_at = closed_at;
+                self.observed.clear();
+            }
+            if closed_at == self.closed_at {
+                self.observed.insert(alert.id.clone());
+            }
+            closed.push((alert, report));
+        }
+        closed
+    }
+}
+
+/// Failures of the parts of a step.
+#[derive(Debug, Default)]
+struct StepErrors(Vec<anyhow::Error>);
+
+impl StepErrors {
+    /// Returns the result of the part of the step, or logs and counts its error.
+    fn check<T>(&mut self, scope: &'static str, result: anyhow::Result<T>) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(err) => {
+                tracing::warn!("{:#}", err);
+                EXPORTER_METRICS.update_errors[&(scope, error_kind(&err))].inc();
+                self.0.push(err);
+                None
+            }
+        }
+    }
+
+    /// Fails with the first error if any part of the step failed.
+    fn into_result(self) -> anyhow::Result<()> {
+        let mut errors = self.0.into_iter();
+        let Some(first) = errors.next() else {
+            return Ok(());
+        };
+        match errors.len() {
+            0 => Err(first),
+            others => {
+                Err(first.context(format!("Update failed, {others} more errors were logged")))
+            }
+        }
+    }
+}
+
+/// Classifies the error for the metrics.
+fn error_kind(err: &anyhow::Error) -> &'static str {
+    let Some(err) = err
+        .chain()
+        .find_map(|err| err.downcast_ref::<ClientError>())
+    else {
+        return "other";
+    };
+    match (err.status().map(|status| status.as_u16()), err) {
+        (Some(429), _) => "rate_limited",
+        (Some(401 | 403), _) => "unauthorized",
+        (Some(500..), _) => "server",
+        (Some(_), _) => "request",
+        (None, ClientError::Client(err)) if err.is_decode() => "invalid_response",
+        (None, ClientError::Client(_) | ClientError::Io(_)) => "network",
+        (None, _) => "other",
+    }
+}
+
+/// Spreads the delay randomly by up to the `jitter` fraction of it in both directions,
+/// so that the updates of the domains drift apart instead of happening in bursts.
+fn jittered(delay: Duration, jitter: f64) -> Duration {
+    delay.mul_f64(1.0 + jitter * (2.0 * fastrand::f64() - 1.0))
+}
+
+/// Returns the delay before retrying a failed update. The delay doubles with each consecutive
+/// failure, and is capped by the polling interval.
+fn retry_delay(consecutive_failures: u32, polling_interval: Duration) -> Duration {
+    let backoff = 2_u32.saturating_pow(consecutive_failures.saturating_sub(1));
+    MIN_RETRY_DELAY
+        .saturating_mul(backoff)
+        .min(polling_interval)
+}
+
+#[cfg(test)]
+mod tests {
+    use opsgenie_mock::{Failure, MockServer, MockState};
+
+    use super::*;
+    use crate::test_utils::config;
+
+    fn encoded_metrics() -> String {
+        let registry = vise::MetricsCollection::default().collect();
+        let mut buffer = String::new();
+        registry
+            .encode(&mut buffer, vise::Format::OpenMetricsForPrometheus)
+            .unwrap();
+        buffer
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn run_stops_on_shutdown() {
+        let mut state = MockState::new();
+        let team = state.add_team("shutdown_team", &["neo"]);
+        let schedule = state.add_schedule("shutdown_schedule", &team);
+        state.set_on_call(&schedule, &["neo"]);
+        let server = MockServer::start(state).await;
+        let updater = OpsgenieUpdater::new(&config(&server, &[])).unwrap();
+
+        // Nothing is updated once the shutdown is requested.
+        updater.run(async {}).await;
+        assert_eq!(
+            on_call(&updater, "shutdown_team", "shutdown_schedule", "neo"),
+            None
+        );
+
+        let (stop, stopped) = tokio::sync::oneshot::channel();
+        let stop_after_update = async {
+            while on_call(&updater, "shutdown_team", "shutdown_schedule", "neo").is_none() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+            stop.send(()).unwrap();
+        };
+        tokio::join!(
+            updater.run(async {
+                stopped.await.ok();
+            }),
+            stop_after_update,
+        );
+        assert_eq!(
+            on_call(&updater, "shutdown_team", "shutdown_schedule", "neo"),
+            Some(OnCallStatus::OnCall)
+        );
+    }
+
+    fn on_call(
+        updater: &OpsgenieUpdater,
+        team: &str,
+        schedule: &str,
+        user: &str,
+    ) -> Option<OnCallStatus> {
+        let snapshot = updater.snapshot.lock().unwrap();
+        let statuses = snapshot.on_call.get(&(team.into(), schedule.into()))?;
+        statuses.get(user).copied()
+    }
+
+    fn alerts(updater: &OpsgenieUpdater, team: &str, status: &str, priority: &str) -> Option<u64> {
+        let snapshot = updater.snapshot.lock().unwrap();
+        let alerts = snapshot.alerts.get(team)?;
+        alerts.counts.get(&(status, priority.into())).copied()
+    }
+
+    // Time is paused to skip the delays between the rate limited requests.
+    #[tokio::test(start_paused = true)]
+    async fn step_exports_metrics() {
+        let mut state = MockState::new();
+        let team = state.add_team("step_team", &["neo", "trinity", "morpheus"]);
+        let schedule = state.add_schedule("step_schedule", &team);
+        state.set_on_call(&schedule, &["neo"]);
+        let now = Utc::now().fixed_offset();
+        state.add_forwarding_rule(
+            "neo",
+            "trinity",
+            now - chrono::Duration::hours(1),
+            now + chrono::Duration::hours(1),
+        );
+        let responder = state.team_responder(&team);
+        let alert = state.add_alert("Disk is full");
+        alert.priority = "P1".into();
+        alert.responders.push(responder.clone());
+        let alert = state.add_alert("Disk was full");
+        alert.priority = "P1".into();
+        alert.status = "closed".into();
+        alert.responders.push(responder);
+        let server = MockServer::start(state).await;
+
+        let updater = OpsgenieUpdater::new(&config(&server, &[])).unwrap();
+        updater.refresh(true).await.unwrap();
+
+        let on_call = |user| on_call(&updater, "step_team", "step_schedule", user);
+        // Neo forwards the notifications to Trinity.
+        assert_eq!(on_call("neo"), Some(OnCallStatus::NotOnCall));
+        assert_eq!(on_call("trinity"), Some(OnCallStatus::OnCall));
+        assert_eq!(on_call("morpheus"), Some(OnCallStatus::NotOnCall));
+        let forwarding_active = |from: &str, to: &str| {
+            let snapshot = updater.snapshot.lock().unwrap();
+            snapshot
+                .forwarding_active
+                .get(&(from.into(), to.into()))
+                .copied()
+        };
+        assert_eq!(forwarding_active("neo", "trinity"), Some(true));
+
+        server.state().forwarding_rules.clear();
+        updater.refresh(true).await.unwrap();
+        assert_eq!(forwarding_active("neo", "trinity"), None);
+        assert_eq!(on_call("neo"), Some(OnCallStatus::OnCall));
+        assert_eq!(on_call("trinity"), Some(OnCallStatus::NotOnCall));
+        assert_eq!(alerts(&updater, "step_team", "total", "P1"), Some(2));
+        assert_eq!(alerts(&updater, "step_team", "open", "P1"), Some(1));
+        assert_eq!(alerts(&updater, "step_team", "total", "P2"), Some(0));
+        let snapshot = updater.snapshot.lock().unwrap();
+        let open_ages = &snapshot.alerts["step_team"].open_ages;
+        assert_eq!(open_ages["P1"].buckets, [1; 7]);
+        assert!(!open_ages.contains_key("P2"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn open_alert_ages_match_open_count() {
+        let mut state = MockState::new();
+        let team = state.add_team("truncated_team", &["neo"]);
+        state.add_schedule("truncated_schedule", &team);
+        let responder = state.team_responder(&team);
+        let count = MAX_ALERTS_TO_FETCH + 5;
+        for i in 0..count {
+            let alert = state.add_alert(&format!("Disk {i} is full"));
+            alert.priority = "P1".into();
+            alert.responders.push(responder.clone());
+        }
+        let server = MockServer::start(state).await;
+
+        let updater = OpsgenieUpdater::new(&config(&server, &[])).unwrap();
+        updater.refresh(true).await.unwrap();
+
+        let count = count as u64;
+        assert_eq!(
+            alerts(&updater, "truncated_team", "open", "P1"),
+            Some(count)
+        );
+        let snapshot = updater.snapshot.lock().unwrap();
+        let buckets = snapshot.alerts["truncated_team"].open_ages["P1"].buckets;
+        assert_eq!(buckets[0], MAX_ALERTS_TO_FETCH as u64);
+        assert_eq!(*buckets.last().unwrap(), count);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn failures_are_isolated() {
+        let mut state = MockState::new();
+        let broken_team = state.add_team("isolated_broken_team", &["neo"]);
+        let broken_schedule = state.add_schedule("isolated_broken_schedule", &broken_team);
+        state.set_on_call(&broken_schedule, &["neo"]);
+        let team = state.add_team("isolated_team", &["trinity", "morpheus"]);
+        let schedule = state.add_schedule("isolated_schedule", &team);
+        state.set_on_call(&schedule, &["trinity"]);
+        let responder = state.team_responder(&broken_team);
+        state.add_alert("Disk is full").responders.push(responder);
+        let server = MockServer::start(state).await;
+        let on_calls_path = format!("/v2/schedules/{broken_schedule}/on-calls");
+        server.fail_path(&on_calls_path, Failure::Unavailable, 1);
+
+        let updater = OpsgenieUpdater::new(&config(&server, &[])).unwrap();
+        let err = updater.refresh(true).await.unwrap_err();
+        assert!(
+            format!("{err:#}").contains("isolated_broken_schedule"),
+            "{err:#}"
+        );
+
+        assert_eq!(
+            on_call(&updater, "isolated_team", "isolated_schedule", "trinity"),
+            Some(OnCallStatus::OnCall)
+        );
+        assert_eq!(
+            on_call(&updater, "isolated_team", "isolated_schedule", "morpheus"),
+            Some(OnCallStatus::NotOnCall)
+        );
+        assert_eq!(
+            on_call(
+                &updater,
+                "isolated_broken_team",
+                "isolated_broken_schedule",
+                "neo"
+            ),
+            None
+        );
+        // Alerts of the team with the broken schedule are still updated.
+        assert_eq!(
+            alerts(&updater, "isolated_broken_team", "open", "P3"),
+            Some(1)
+        );
+        assert!(EXPORTER_METRICS.update_errors[&("schedule", "server")].get() >= 1);
+
+        // The schedule is updated on the next step.
+        updater.refresh(true).await.unwrap();
+        assert_eq!(
+            on_call(
+                &updater,
+                "isolated_broken_team",
+                "isolated_broken_schedule",
+                "neo"
+            ),
+            Some(OnCallStatus::OnCall)
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn stale_series_are_removed() {
+        let mut state = MockState::new();
+        let ops = state.add_team("ops", &["neo", "trinity"]);
+        let ops_schedule = state.add_schedule("ops_schedule", &ops);
+        state.set_on_call(&ops_schedule, &["neo"]);
+        let dev = state.add_team("dev", &["morpheus"]);
+        state.add_schedule("dev_schedule", &dev);
+        let server = MockServer::start(state).await;
+
+        let updater = OpsgenieUpdater::new(&config(&server, &[])).unwrap();
+        updater.refresh(true).await.unwrap();
+        assert_eq!(
+            on_call(&updater, "ops", "ops_schedule", "trinity"),
+            Some(OnCallStatus::NotOnCall)
+        );
+        assert_eq!(alerts(&updater, "dev", "total", "P1"), Some(0));
+
+        {
+            // Trinity leaves the team, and the dev team is deleted with its schedule.
+            let mut state = server.state();
+            let members = state.teams[0].members.as_mut().unwrap();
+            members.retain(|member| member.user.username.as_deref() != Some("trinity"));
+            state.teams.retain(|team| team.id != dev);
+            state
+                .schedules
+                .retain(|schedule| schedule.owner_team.id != dev);
+        }
+        server.fail_path(
+            &format!("/v2/schedules/{ops_schedule}/on-calls"),
+            Failure::Unavailable,
+            1,
+        );
+        // The series of the schedule that failed to update are kept.
+        updater.refresh(true).await.unwrap_err();
+        assert_eq!(
+            on_call(&updater, "ops", "ops_schedule", "trinity"),
+            Some(OnCallStatus::NotOnCall)
+        );
+        assert_eq!(on_call(&updater, "dev", "dev_schedule", "morpheus"), None);
+        assert_eq!(alerts(&updater, "dev", "total", "P1"), None);
+
+        updater.refresh(true).await.unwrap();
+        assert_eq!(on_call(&updater, "ops", "ops_schedule", "trinity"), None);
+        assert_eq!(
+            on_call(&updater, "ops", "ops_schedule", "neo"),
+            Some(OnCallStatus::OnCall)
+        );
+        assert_eq!(alerts(&updater, "ops", "total", "P1"), Some(0));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn closed_alerts_are_observed_once() {
+        let mut state = MockState::new();
+        let team = state.add_team("closing_team", &["neo"]);
+        state.add_schedule("closing_schedule", &team);
+        let server = MockServer::start(state).await;
+
+        let updater = OpsgenieUpdater::new(&config(&server, &[])).unwrap();
+        updater.refresh(true).await.unwrap();
+        {
+            let mut state = server.state();
+            let responder = state.team_responder(&team);
+            let now = Utc::now().fixed_offset();
+            // Closed after the first step.
+            let alert = state.add_alert("Disk is full");
+            alert.status = "closed".into();
+            alert.created_at = now - chrono::Duration::minutes(10);
+            alert.report = Some(Report {
+                ack_time: Some(60_000),
+                close_time: Some(600_000),
+                acknowledged_by: Some("neo".into()),
+                closed_by: Some("neo".into()),
+            });
+            alert.responders.push(responder.clone());
+            // Closed before the first step, and updated since then.
+            let alert = state.add_alert("Disk was full");
+            alert.status = "closed".into();
+            alert.created_at = now - chrono::Duration::days(1);
+            alert.report = Some(Report {
+                ack_time: None,
+                close_time: Some(60_000),
+                acknowledged_by: None,
+                closed_by: None,
+            });
+            alert.responders.push(responder);
+        }
+
+        let observed_once = |metric: &str| {
+            let entry = format!(
+                r#"opsgenie_{metric}_count{{team="closing_team",priority="P3",acknowledged_by="neo"}} 1"#
+            );
+            encoded_metrics().contains(&entry)
+        };
+        updater.refresh(true).await.unwrap();
+        assert!(observed_once("alert_time_to_ack_seconds"));
+        assert!(observed_once("alert_time_to_close_seconds"));
+        updater.refresh(true).await.unwrap();
+        assert!(observed_once("alert_time_to_close_seconds"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn many_closed_alerts_are_observed() {
+        let mut state = MockState::new();
+        let team = state.add_team("many_closing_team", &["neo"]);
+        state.add_schedule("many_closing_schedule", &team);
+        let server = MockServer::start(state).await;
+
+        let updater = OpsgenieUpdater::new(&config(&server, &[])).unwrap();
+        updater.refresh(true).await.unwrap();
+        let count = MAX_ALERTS_TO_FETCH + 5;
+        {
+            let mut state = server.state();
+            let responder = state.team_responder(&team);
+            let now = Utc::now().fixed_offset();
+            for i in 0..count {
+                let alert = state.add_alert(&format!("Disk {i} is full"));
+                alert.status = "closed".into();
+                alert.created_at = now - chrono::Duration::minutes(10);
+                alert.report = Some(Report {
+                    ack_time: None,
+                    close_time: Some(600_000 + i as u64),
+                    acknowledged_by: None,
+                    closed_by: None,
+                });
+                alert.responders.push(responder.clone());
+            }
+        }
+
+        updater.refresh(true).await.unwrap();
+        let entry = format!(
+            r#"opsgenie_alert_time_to_close_seconds_count{{team="many_closing_team",priority="P3",acknowledged_by=""}} {count}"#
+        );
+        assert!(encoded_metrics().contains(&entry));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn alerts_are_filtered() {
+        let mut state = MockState::new();
+        let team = state.add_team("filtered_team", &["neo"]);
+        state.add_schedule("filtered_schedule", &team);
+        let responder = state.team_responder(&team);
+        let alert = state.add_alert("Disk is full");
+        alert.tags = Some(vec!["production".into()]);
+        alert.responders.push(responder.clone());
+        state.add_alert("Disk is full").responders.push(responder);
+        let server = MockServer::start(state).await;
+
+        let config = config(&server, &[("ALERT_FILTER", "tag:production")]);
+        let updater = OpsgenieUpdater::new(&config).unwrap();
+        updater.refresh(true).await.unwrap();
+        assert_eq!(alerts(&updater, "filtered_team", "total", "P3"), Some(1));
+        assert_eq!(alerts(&updater, "filtered_team", "open", "P3"), Some(1));
+
+        // Invalid filters are rejected when the configuration is loaded.
+        let vars = [
+            ("OPSGENIE_BASE_URL", server.url().to_string()),
+            ("OPSGENIE_API_KEY", opsgenie_mock::API_KEY.to_owned()),
+            ("ALERT_FILTER", "tag:(production".to_owned()),
+        ];
+        let vars = vars.map(|(key, value)| (key.to_owned(), value));
+        let err = envy::from_iter::<_, Config>(vars).unwrap_err();
+        assert!(err.to_string().contains("invalid ALERT_FILTER"), "{err}");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn alerts_are_counted_by_service() {
+        let mut state = MockState::new();
+        let team = state.add_team("service_team", &["neo"]);
+        state.add_schedule("service_schedule", &team);
+        state.add_service("checkout", &team);
+        let payments = state.add_service("payments", &team);
+        // Services of different teams may have the same name.
+        let other_team = state.add_team("other_service_team", &["trinity"]);
+        state.add_schedule("other_service_schedule", &other_team);
+        state.add_service("checkout", &other_team);
+        state.add_alert("Checkout is down").tags = Some(vec!["checkout".into()]);
+        let alert = state.add_alert("Checkout was down");
+        alert.status = "closed".into();
+        alert.tags = Some(vec!["checkout".into(), "payments".into()]);
+        let server = MockServer::start(state).await;
+
+        let updater =
+            OpsgenieUpdater::new(&config(&server, &[("EXPORT_SERVICES", "true")])).unwrap();
+        updater.refresh(true).await.unwrap();
+        let service_alerts = |team: &str, service: &str| {
+            let snapshot = updater.snapshot.lock().unwrap();
+            let info = ServiceInfo {
+                team: team.into(),
+                name: service.into(),
+            };
+            let (id, _) = snapshot
+                .services
+                .iter()
+                .find(|(_, other)| **other == info)?;
+            snapshot.service_alerts.get(id).cloned()
+        };
+        assert_eq!(
+            service_alerts("service_team", "checkout"),
+            Some([("total", 2), ("open", 1)].into())
+        );
+        assert_eq!(
+            service_alerts("other_service_team", "checkout"),
+            Some([("total", 2), ("open", 1)].into())
+        );
+        assert_eq!(
+            service_alerts("service_team", "payments"),
+            Some([("total", 1), ("open", 0)].into())
+        );
+
+        // Series of the services moved to another team are removed, as well as the deleted ones.
+        {
+            let mut state = server.state();
+            let service = state
+                .services
+                .iter_mut()
+                .find(|service| service.id == payments);
+            service.unwrap().team_id = other_team;
+            state
+                .services
+                .retain(|service| !(service.name == "checkout" && service.team_id != team));
+        }
+        updater.refresh(true).await.unwrap();
+        assert_eq!(service_alerts("service_team", "payments"), None);
+        assert_eq!(
+            service_alerts("other_service_team", "payments"),
+            Some([("total", 1), ("open", 0)].into())
+        );
+        assert_eq!(service_alerts("other_service_team", "checkout"), None);
+        assert_eq!(updater.snapshot.lock().unwrap().services.len(), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn open_incidents_are_counted_by_service() {
+        let mut state = MockState::new();
+        let team = state.add_team("incident_team", &["neo"]);
+        let checkout = state.add_service("checkout", &team);
+        let payments = state.add_service("payments", &team);
+        state.add_incident("Checkout is down", &[&checkout, &payments]);
+        state.add_incident("Checkout was down", &[&checkout]).status = "resolved".into();
+        state.add_incident("Search is down", &["unknown_service"]);
+        let server = MockServer::start(state).await;
+
+        let updater =
+            OpsgenieUpdater::new(&config(&server, &[("EXPORT_SERVICES", "true")])).unwrap();
+        updater.refresh(true).await.unwrap();
+        let open_incidents = |service: &str| {
+            let snapshot = updater.snapshot.lock().unwrap();
+            snapshot.service_open_incidents.get(service).copied()
+        };
+        assert_eq!(open_incidents(&checkout), Some(1));
+        assert_eq!(open_incidents(&payments), Some(1));
+
+        server.state().incidents[0].status = "closed".into();
+        updater.refresh(true).await.unwrap();
+        assert_eq!(open_incidents(&checkout), Some(0));
+        assert_eq!(open_incidents("unknown_service"), None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn deployment_state_changes_are_counted() {
+        let mut state = MockState::new();
+        let team = state.add_team("deploying_team", &["neo"]);
+        let service = state.add_service("deploying_service", &team);
+        state
+            .add_deployment("Deployed before the start", &[&service])
+            .state = DeploymentState::Successful;
+        let server = MockServer::start(state).await;
+        let deployments = |state: DeploymentState| {
+            METRICS.deployments[&("deploying_service".into(), state.as_str())].get()
+        };
+
+        let updater =
+            OpsgenieUpdater::new(&config(&server, &[("EXPORT_DEPLOYMENTS", "true")])).unwrap();
+        updater.refresh(true).await.unwrap();
+        // Deployments seen on the first update may have been counted before a restart.
+        assert_eq!(deployments(DeploymentState::Successful), 0);
+
+        let id = server
+            .state()
+            .add_deployment("Deployed after the start", &[&service])
+            .id
+            .clone();
+        updater.refresh(true).await.unwrap();
+        assert_eq!(deployments(DeploymentState::Started), 1);
+        updater.refresh(true).await.unwrap();
+        assert_eq!(deployments(DeploymentState::Started), 1);
+
+        {
+            let mut state = server.state();
+            let deployment = state.deployments.iter_mut().find(|d| d.id == id).unwrap();
+            deployment.state = DeploymentState::Successful;
+        }
+        updater.refresh(true).await.unwrap();
+        assert_eq!(deployments(DeploymentState::Successful), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn deployments_are_listed_down_to_the_seen_ones() {
+        let mut state = MockState::new();
+        let team = state.add_team("paging_team", &["neo"]);
+        let service = state.add_service("paging_service", &team);
+        state.add_deployment("Deployed before the start", &[&service]);
+        let server = MockServer::start(state).await;
+        let started = || METRICS.deployments[&("paging_service".into(), "started")].get();
+
+        let updater =
+            OpsgenieUpdater::new(&config(&server, &[("EXPORT_DEPLOYMENTS", "true")])).unwrap();
+        updater.refresh(true).await.unwrap();
+        assert_eq!(started(), 0);
+
+        // More deployments than fit in a page were started since the last update.
+        for i in 0..250 {
+            server
+                .state()
+                .add_deployment(&format!("Deployment {i}"), &[&service]);
+        }
+        updater.refresh(true).await.unwrap();
+        assert_eq!(started(), 250);
+        updater.refresh(true).await.unwrap();
+        assert_eq!(started(), 250);
+    }
+
+    #[test]
+    fn watermark_observes_each_alert_once() {
+        let start = Utc::now();
+        let mut state = MockState::new();
+        let mut closed_alert = |closed_at: DateTime<Utc>| {
+            let alert = state.add_alert("Disk is full");
+            alert.created_at = (closed_at - chrono::Duration::minutes(1)).fixed_offset();
+            alert.report = Some(Report {
+                ack_time: None,
+                close_time: Some(60_000),
+                acknowledged_by: None,
+                closed_by: None,
+            });
+            alert.clone()
+        };
+        let first = closed_alert(start + chrono::Duration::seconds(1));
+        let second = closed_alert(start + chrono::Duration::seconds(1));
+        let before_start = closed_alert(start - chrono::Duration::seconds(1));
+        fn ids<'a>(closed: Vec<(&'a Alert, &Report)>) -> Vec<&'a str> {
+            closed
+                .into_iter()
+                .map(|(alert, _)| alert.id.as_str())
+                .collect()
+        }
+
+        let mut watermark = ClosedAlertsWatermark::new(start);
+        let alerts = [first.clone()];
+        assert_eq!(ids(watermark.advance(&alerts)), [first.id.as_str()]);
+        // The second alert was closed at the same time as the first one.
+        let alerts = [first, second.clone(), before_start];
+        assert_eq!(ids(watermark.advance(&alerts)), [second.id.as_str()]);
+        assert!(watermark.advance(&alerts).is_empty());
+        assert_eq!(watermark.closed_at, start + chrono::Duration::seconds(1));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn failure_is_reported() {
+        let server = MockServer::start(MockState::new()).await;
+        server.fail_next(Failure::RateLimited, 1);
+
+        let config = config(
+            &server,
+            &[
+                ("ALERT_ON_FAILURE", "true"),
+                ("FAILURE_ALERT_THRESHOLD", "2"),
+                ("POLLING_JITTER", "0"),
+            ],
+        );
+        let updater = OpsgenieUpdater::new(&config).unwrap();
+        let mut state = DomainState::default();
+        let result = updater.update(DataDomain::Teams, &mut state).await;
+        result.unwrap_err();
+        assert_eq!(state.consecutive_failures, 1);
+        assert_eq!(state.next_update - Instant::now(), MIN_RETRY_DELAY);
+        // A single failure is not reported.
+        assert!(server.state().alerts.is_empty());
+
+        server.fail_next(Failure::RateLimited, 1);
+        let result = updater.update(DataDomain::Teams, &mut state).await;
+        result.unwrap_err();
+        assert_eq!(state.consecutive_failures, 2);
+        assert_eq!(state.next_update - Instant::now(), MIN_RETRY_DELAY * 2);
+        assert!(EXPORTER_METRICS.update_errors[&("teams", "rate_limited")].get() >= 1);
+        let requests = |status: &str| {
+            EXPORTER_METRICS.api_requests[&("GET".into(), "v2/teams".into(), status.into())].get()
+        };
+        assert!(requests("429") >= 1);
+        assert!(EXPORTER_METRICS.api_rate_limited[&"v2/teams".into()].get() >= 1);
+        {
+            let state = server.state();
+            assert_eq!(state.alerts.len(), 1);
+            assert_eq!(state.alerts[0].alias.as_deref(), Some(FAILURE_ALERT_ALIAS));
+        }
+
+        // The updater recovers once the API is available again.
+        let result = updater.update(DataDomain::Teams, &mut state).await;
+        result.unwrap();
+        assert_eq!(state.consecutive_failures, 0);
+        assert_eq!(
+            state.next_update - Instant::now(),
+            Duration::from_secs(3600)
+        );
+        assert!(requests("200") >= 1);
+        assert!(EXPORTER_METRICS.last_success_timestamp[&"teams"].get() > 0);
+        assert_eq!(server.state().alerts[0].status, "closed");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn domains_are_updated_independently() {
+        let mut state = MockState::new();
+        let team = state.add_team("independent_team", &["neo"]);
+        let schedule = state.add_schedule("independent_schedule", &team);
+        state.set_on_call(&schedule, &["neo"]);
+        let server = MockServer::start(state).await;
+
+        let config = config(
+            &server,
+            &[
+                ("POLLING_JITTER", "0"),
+                ("ALERTS_POLLING_INTERVAL_SECS", "60"),
+            ],
+        );
+        let updater = OpsgenieUpdater::new(&config).unwrap();
+        let mut state = DomainState::default();
+        // On-call statuses need the schedules.
+        let result = updater.update(DataDomain::OnCall, &mut state).await;
+        assert!(format!("{:#}", result.unwrap_err()).contains("not fetched yet"));
+        assert_eq!(state.consecutive_failures, 1);
+        updater.refresh(false).await.unwrap();
+
+        // Cached schedules are used, so listing them again is not needed.
+        server.fail_path("/v2/schedules", Failure::Unavailable, 1);
+        let result = updater.update(DataDomain::OnCall, &mut state).await;
+        result.unwrap();
+        assert_eq!(state.consecutive_failures, 0);
+        assert_eq!(state.next_update - Instant::now(), Duration::from_secs(300));
+        assert_eq!(
+            on_call(&updater, "independent_team", "independent_schedule", "neo"),
+            Some(OnCallStatus::OnCall)
+        );
+        assert_eq!(alerts(&updater, "independent_team", "total", "P1"), Some(0));
+
+        // Only the domains that are due are updated.
+        tokio::time::advance(Duration::from_secs(60)).await;
+        {
+            let mut state = server.state();
+            let schedule = state.schedules.iter_mut().next().unwrap();
+            schedule.name = "renamed_schedule".into();
+        }
+        updater.refresh(false).await.unwrap();
+        let updater = &updater;
+        let due = |domain| async move {
+            let state = updater.domains[&domain].lock().await;
+            state.next_update - Instant::now()
+        };
+        assert_eq!(due(DataDomain::Alerts).await, Duration::from_secs(60));
+        assert!(due(DataDomain::OnCall).await < Duration::from_secs(300));
+        assert_eq!(
+            on_call(updater, "independent_team", "renamed_schedule", "neo"),
+            None
+        );
+    }
+
+    #[test]
+    fn delays_are_jittered() {
+        let delay = Duration::from_secs(100);
+        let delays: Vec<_> = (0..100).map(|_| jittered(delay, 0.1)).collect();
+        assert!(delays
+            .iter()
+            .all(|delay| (90.0..=110.0).contains(&delay.as_secs_f64())));
+        assert!(delays.iter().any(|jittered| *jittered != delay));
+        assert_eq!(jittered(delay, 0.0), delay);
+    }
+
+    #[test]
+    fn retry_delays() {
+        let polling_interval = Duration::from_secs(300);
+        let delays: Vec<_> = (1..=6)
+            .map(|failures| retry_delay(failures, polling_interval).as_secs())
+            .collect();
+        assert_eq!(delays, [15, 30, 60, 120, 240, 300]);
+        assert_eq!(retry_delay(u32::MAX, polling_interval), polling_interval);
+    }
 }